@@ -0,0 +1,73 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use fractulate::{GrowthParams, Mesh};
+use nalgebra::Vector3;
+use rand::SeedableRng;
+use rand_xoshiro::Xoshiro256StarStar;
+
+/// Unit tetrahedron with outward facing triangles.
+fn tetrahedron() -> Mesh {
+    let o = Vector3::zeros();
+    let [x, y, z] = [Vector3::x(), Vector3::y(), Vector3::z()];
+    Mesh::new(vec![[o, y, x], [o, x, z], [o, z, y], [x, y, z]])
+}
+
+fn grow(c: &mut Criterion) {
+    let base = tetrahedron();
+    let mut group = c.benchmark_group("grow");
+    for depth in 1..=4 {
+        let params = GrowthParams {
+            depth,
+            ..GrowthParams::default()
+        };
+        group.bench_with_input(BenchmarkId::from_parameter(depth), &params, |b, params| {
+            b.iter(|| {
+                let mut rng = Xoshiro256StarStar::seed_from_u64(0);
+                fractulate::grow(&base, &base, params, &mut rng).unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn instantiate(c: &mut Criterion) {
+    let base = tetrahedron();
+    let params = GrowthParams {
+        depth: 4,
+        ..GrowthParams::default()
+    };
+    let mut rng = Xoshiro256StarStar::seed_from_u64(0);
+    let instances = fractulate::instances(&base, &base, &params, &mut rng).unwrap();
+    let indexed = fractulate::index(&base);
+    let mut group = c.benchmark_group("instantiate");
+    group.bench_function("soup", |b| {
+        b.iter(|| fractulate::instantiate(&base, &base, black_box(&instances)))
+    });
+    group.bench_function("indexed", |b| {
+        b.iter(|| fractulate::instantiate_indexed(&indexed, &indexed, black_box(&instances)))
+    });
+    group.finish();
+}
+
+fn select(c: &mut Criterion) {
+    let base = tetrahedron();
+    let mut rng = Xoshiro256StarStar::seed_from_u64(0);
+    c.bench_function("select", |b| {
+        b.iter(|| fractulate::select(&mut rng, black_box(&base)))
+    });
+}
+
+/// Draws from samplers of a range of sizes, to find where bisecting starts to beat scanning.
+fn sample(c: &mut Criterion) {
+    let mut rng = Xoshiro256StarStar::seed_from_u64(0);
+    let mut group = c.benchmark_group("sample");
+    for len in [4, 8, 16, 32, 64, 128, 1024] {
+        let sampler = fractulate::WeightedSampler::new((0..len).map(|i| i as f32 + 1.0)).unwrap();
+        group.bench_with_input(BenchmarkId::from_parameter(len), &sampler, |b, sampler| {
+            b.iter(|| sampler.sample(&mut rng))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, grow, instantiate, select, sample);
+criterion_main!(benches);
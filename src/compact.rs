@@ -0,0 +1,116 @@
+use nalgebra::Vector3;
+use std::collections::HashMap;
+
+use crate::Triangle;
+
+/// A mesh held in a fraction of the memory of an [`IndexedMesh`](crate::IndexedMesh), for
+/// fractals too big to gather otherwise. Each vertex is stored as three 16 bit steps across the
+/// mesh's bounding box, and faces index vertices with 32 bits.
+///
+/// Lossy: vertices move by up to [`max_error`](Self::max_error) to the nearest step, and those
+/// that land on the same one are merged, the way [`weld`](crate::weld) would merge them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CompactMesh {
+    min: Vector3<f32>,
+    /// Size of a step along each axis.
+    step: Vector3<f32>,
+    vertices: Vec<[u16; 3]>,
+    faces: Vec<[u32; 3]>,
+}
+
+impl CompactMesh {
+    /// Gather `triangles`, which must all lie in the box between `min` and `max`. Triangles are
+    /// kept in order, even those that become degenerate.
+    pub fn new(
+        (min, max): (Vector3<f32>, Vector3<f32>),
+        triangles: impl IntoIterator<Item = Triangle>,
+    ) -> Self {
+        let step = (max - min) / u16::MAX as f32;
+        let quantize = |v: &Vector3<f32>| -> [u16; 3] {
+            (v - min)
+                .zip_map(&step, |offset, step| match step > 0.0 {
+                    true => (offset / step).round().clamp(0.0, u16::MAX as f32) as u16,
+                    false => 0,
+                })
+                .into()
+        };
+        let mut ret = CompactMesh {
+            min,
+            step,
+            ..Default::default()
+        };
+        let mut indices = HashMap::new();
+        for triangle in triangles {
+            let face = triangle.map(|v| {
+                *indices
+                    .entry(quantize(&v))
+                    .or_insert_with_key(|&quantized| {
+                        ret.vertices.push(quantized);
+                        u32::try_from(ret.vertices.len() - 1).expect("too many vertices")
+                    })
+            });
+            ret.faces.push(face);
+        }
+        ret
+    }
+
+    pub fn len(&self) -> usize {
+        self.faces.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.faces.is_empty()
+    }
+
+    /// Furthest any vertex can have moved, half the diagonal of a step.
+    pub fn max_error(&self) -> f32 {
+        self.step.norm() / 2.0
+    }
+
+    pub fn vertices(&self) -> impl ExactSizeIterator<Item = Vector3<f32>> + '_ {
+        self.vertices.iter().map(|&v| self.vertex(v))
+    }
+
+    pub fn faces(&self) -> impl ExactSizeIterator<Item = [usize; 3]> + '_ {
+        self.faces.iter().map(|face| face.map(|i| i as usize))
+    }
+
+    /// Expand back into a triangle soup.
+    pub fn triangles(&self) -> impl ExactSizeIterator<Item = Triangle> + '_ {
+        self.faces
+            .iter()
+            .map(|face| face.map(|i| self.vertex(self.vertices[i as usize])))
+    }
+
+    fn vertex(&self, quantized: [u16; 3]) -> Vector3<f32> {
+        self.min + Vector3::from(quantized.map(f32::from)).component_mul(&self.step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bounding_box, weld, Mesh};
+    use nalgebra::Matrix4;
+
+    #[test]
+    fn compact_subdivided_tetrahedron() {
+        // stretched so the steps differ between axes
+        let stretch = Matrix4::new_nonuniform_scaling(&Vector3::new(1.0, 1.0, 3.0));
+        let tetrahedron = crate::tetrahedron().transformed(&stretch);
+        let fine = tetrahedron.subdivided().subdivided();
+        let compact = CompactMesh::new(bounding_box(&fine).unwrap(), fine.iter().copied());
+        assert_eq!(compact.len(), fine.len());
+        assert_eq!(compact.vertices().len(), weld(&fine, 0.0).vertices.len());
+        assert!(compact.max_error() < 3e-5);
+        for (gathered, exact) in compact.triangles().zip(fine.iter()) {
+            for (a, b) in gathered.iter().zip(exact) {
+                assert!((a - b).norm() <= compact.max_error() + 1e-6);
+            }
+        }
+        // a flat mesh has no steps across it, but still keeps its plane
+        let flat = Mesh::new(vec![tetrahedron[0]]);
+        let compact = CompactMesh::new(bounding_box(&flat).unwrap(), flat.iter().copied());
+        assert_eq!(compact.triangles().collect::<Vec<_>>(), flat.to_vec());
+    }
+}
@@ -0,0 +1,144 @@
+use nalgebra::Vector3;
+use std::collections::{HashMap, HashSet};
+
+use crate::mesh::point_bounds;
+use crate::IndexedMesh;
+
+/// Finest grid tried, in cells along the longest side of the mesh's bounds.
+const MAX_RESOLUTION: u32 = 1 << 16;
+
+/// Cut `mesh` down to at most `target` faces by vertex clustering. Space is cut into a grid of
+/// cubes, every vertex in a cube is merged into one at their average position, and faces left with
+/// fewer than three distinct vertices are dropped, along with duplicate faces. The finest grid that
+/// gets down to `target` is used, so the result has as much detail as fits.
+///
+/// Coarse, but fast and robust to the overlapping, non-manifold surfaces fractals are made of.
+/// Cubes don't respect the surface, so features thinner than a cube collapse. Neighbors come from
+/// shared vertices, so weld the mesh first.
+pub fn decimate(mesh: &IndexedMesh, target: usize) -> IndexedMesh {
+    if mesh.faces.len() <= target {
+        return mesh.clone();
+    }
+    let Some(bounds) = vertex_bounds(&mesh.vertices) else {
+        return mesh.clone();
+    };
+    // more cells keep more faces, give or take the odd triangle, so search for the most cells
+    // that fit
+    let (mut fits, mut too_many) = (1, MAX_RESOLUTION + 1);
+    let mut best = cluster(mesh, bounds, fits);
+    while too_many - fits > 1 {
+        let mid = fits + (too_many - fits) / 2;
+        let clustered = cluster(mesh, bounds, mid);
+        if clustered.faces.len() <= target {
+            fits = mid;
+            best = clustered;
+        } else {
+            too_many = mid;
+        }
+    }
+    best
+}
+
+fn vertex_bounds(vertices: &[Vector3<f32>]) -> Option<(Vector3<f32>, f32)> {
+    let (min, max) = point_bounds(vertices)?;
+    Some((min, (max - min).max()))
+}
+
+/// Merge the vertices in each cell of a grid `resolution` cells across the bounds given as the
+/// minimum corner and longest side.
+fn cluster(mesh: &IndexedMesh, (min, side): (Vector3<f32>, f32), resolution: u32) -> IndexedMesh {
+    let cell_size = side / resolution as f32;
+    let cell = |v: &Vector3<f32>| -> [u32; 3] {
+        if cell_size > 0.0 {
+            v.zip_map(&min, |c, min| {
+                (((c - min) / cell_size) as u32).min(resolution - 1)
+            })
+            .into()
+        } else {
+            [0; 3]
+        }
+    };
+
+    // clusters are numbered in the order their first vertex appears
+    let mut clusters = HashMap::new();
+    let mut sums: Vec<(Vector3<f32>, usize)> = Vec::new();
+    let remap: Vec<usize> = mesh
+        .vertices
+        .iter()
+        .map(|v| {
+            let i = *clusters.entry(cell(v)).or_insert_with(|| {
+                sums.push((Vector3::zeros(), 0));
+                sums.len() - 1
+            });
+            sums[i].0 += v;
+            sums[i].1 += 1;
+            i
+        })
+        .collect();
+
+    let mut seen = HashSet::new();
+    let faces: Vec<[usize; 3]> = mesh
+        .faces
+        .iter()
+        .map(|face| face.map(|i| remap[i]))
+        .filter(|&[a, b, c]| a != b && b != c && c != a)
+        .filter(|face| {
+            // the same face twice, however it's rotated, is only kept once
+            let first = (0..3).min_by_key(|&i| face[i]).unwrap();
+            seen.insert([face[first], face[(first + 1) % 3], face[(first + 2) % 3]])
+        })
+        .collect();
+
+    let mut ret = IndexedMesh {
+        vertices: sums.iter().map(|(sum, n)| sum / *n as f32).collect(),
+        faces,
+    };
+    ret.remove_unused_vertices();
+    ret
+}
+
+impl IndexedMesh {
+    /// Drop vertices no face uses, renumbering the rest in order.
+    pub(crate) fn remove_unused_vertices(&mut self) {
+        let mut used = vec![false; self.vertices.len()];
+        for &i in self.faces.iter().flatten() {
+            used[i] = true;
+        }
+        let mut renumber = vec![0; self.vertices.len()];
+        let mut kept = 0;
+        for (i, &used) in used.iter().enumerate() {
+            if used {
+                self.vertices[kept] = self.vertices[i];
+                renumber[i] = kept;
+                kept += 1;
+            }
+        }
+        self.vertices.truncate(kept);
+        for i in self.faces.iter_mut().flatten() {
+            *i = renumber[*i];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::weld;
+
+    #[test]
+    fn decimate_towards_target() {
+        let tetrahedron = crate::tetrahedron();
+        let fine = weld(&tetrahedron.subdivided().subdivided().subdivided(), 0.0);
+        assert_eq!(fine.faces.len(), 256);
+        for target in [256, 100, 20, 4, 0] {
+            let coarse = decimate(&fine, target);
+            assert!(coarse.faces.len() <= target, "{target}");
+            for v in &coarse.vertices {
+                // still within the tetrahedron's bounds
+                assert!(v.iter().all(|&c| (-1e-6..=1.0 + 1e-6).contains(&c)));
+            }
+        }
+        assert_eq!(decimate(&fine, 256), fine);
+        assert!(decimate(&fine, 100).faces.len() > 4);
+    }
+}
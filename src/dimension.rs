@@ -0,0 +1,139 @@
+use nalgebra::Vector3;
+
+use crate::Triangle;
+
+/// Grids are 2, 4, 8 and so on up to 2^`FINEST` cells along the longest side of the bounds.
+const FINEST: u32 = 7;
+
+/// Most points sampled along a triangle's edge, so one huge triangle can't take forever.
+const MAX_SAMPLES: usize = 1024;
+
+/// Estimates the box counting dimension of a surface, by counting how many cells of finer and
+/// finer grids it passes through. A smooth surface comes out near 2, while a fractal filling space
+/// tends towards 3.
+///
+/// The grids cover fixed bounds, so it needs the bounds of the whole surface up front, but the
+/// triangles can then be added a piece at a time.
+#[derive(Clone, Debug)]
+pub struct BoxCounter {
+    min: Vector3<f32>,
+    /// Longest side of the bounds, the size of every grid.
+    side: f32,
+    grids: Vec<Grid>,
+}
+
+#[derive(Clone, Debug)]
+struct Grid {
+    /// Cells along each axis.
+    cells: usize,
+    /// One bit per cell, set once the surface passes through it.
+    occupied: Vec<u64>,
+}
+
+impl BoxCounter {
+    /// A counter for a surface within the box from `min` to `max`.
+    pub fn new((min, max): (Vector3<f32>, Vector3<f32>)) -> Self {
+        let grids = (1..=FINEST)
+            .map(|k| {
+                let cells = 1 << k;
+                Grid {
+                    cells,
+                    occupied: vec![0; (cells * cells * cells).div_ceil(64)],
+                }
+            })
+            .collect();
+        BoxCounter {
+            min,
+            side: (max - min).max(),
+            grids,
+        }
+    }
+
+    pub fn add(&mut self, triangle: &Triangle) {
+        if self.side <= 0.0 {
+            return;
+        }
+        let [a, b, c] = triangle.map(|v| (v - self.min) / self.side);
+        let longest = [b - a, c - b, a - c]
+            .iter()
+            .map(|edge| edge.norm())
+            .fold(0.0, f32::max);
+        for grid in &mut self.grids {
+            // points at most half a cell apart won't skip over a cell
+            let samples = (longest * grid.cells as f32 * 2.0).ceil() as usize;
+            let samples = samples.clamp(1, MAX_SAMPLES);
+            for i in 0..=samples {
+                for j in 0..=samples - i {
+                    let (u, v) = (i as f32 / samples as f32, j as f32 / samples as f32);
+                    grid.mark(&(a + (b - a) * u + (c - a) * v));
+                }
+            }
+        }
+    }
+
+    pub fn extend<'a>(&mut self, triangles: impl IntoIterator<Item = &'a Triangle>) {
+        for triangle in triangles {
+            self.add(triangle);
+        }
+    }
+
+    /// Slope of the log of the occupied cells against the log of the grid resolution, by least
+    /// squares. `None` if nothing with any size was added.
+    pub fn dimension(&self) -> Option<f64> {
+        let points: Vec<(f64, f64)> = self
+            .grids
+            .iter()
+            .map(|grid| (grid.cells as f64, grid.count() as f64))
+            .filter(|&(_, count)| count > 0.0)
+            .map(|(cells, count)| (cells.ln(), count.ln()))
+            .collect();
+        if points.len() < 2 {
+            return None;
+        }
+        let n = points.len() as f64;
+        let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+        let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+        let covariance: f64 = points
+            .iter()
+            .map(|(x, y)| (x - mean_x) * (y - mean_y))
+            .sum();
+        let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+        Some(covariance / variance)
+    }
+}
+
+impl Grid {
+    /// Mark the cell holding `p`, in coordinates where the grid spans 0 to 1.
+    fn mark(&mut self, p: &Vector3<f32>) {
+        let [x, y, z] = p
+            .map(|c| ((c * self.cells as f32) as usize).min(self.cells - 1))
+            .into();
+        let i = (x * self.cells + y) * self.cells + z;
+        self.occupied[i / 64] |= 1 << (i % 64);
+    }
+
+    fn count(&self) -> usize {
+        self.occupied
+            .iter()
+            .map(|bits| bits.count_ones() as usize)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flat_square_is_two_dimensional() {
+        let v = Vector3::new;
+        let square = [
+            [v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0), v(1.0, 1.0, 0.0)],
+            [v(0.0, 0.0, 0.0), v(1.0, 1.0, 0.0), v(0.0, 1.0, 0.0)],
+        ];
+        let mut counter = BoxCounter::new((v(0.0, 0.0, 0.0), v(1.0, 1.0, 0.0)));
+        counter.extend(&square);
+        let dimension = counter.dimension().unwrap();
+        assert!((dimension - 2.0).abs() < 0.05, "{dimension}");
+    }
+}
@@ -0,0 +1,24 @@
+use noise::{NoiseFn, Perlin};
+use rayon::prelude::*;
+
+use crate::IndexedMesh;
+
+/// Push each vertex along its normal by Perlin noise sampled at its position, for an organic,
+/// lumpy surface. Vertices move by at most about `amplitude`, and `frequency` is how many bumps
+/// there are per unit of distance.
+///
+/// The noise depends only on `seed` and where the vertex is, so the same mesh and seed always
+/// displace the same way. Weld the mesh first, otherwise triangles that should share a vertex
+/// each move their copy along their own normal and split apart.
+pub fn displace(mesh: &mut IndexedMesh, amplitude: f32, frequency: f32, seed: u32) {
+    let noise = Perlin::new(seed);
+    let normals = mesh.vertex_normals();
+    mesh.vertices
+        .par_iter_mut()
+        .zip(&normals)
+        .for_each(|(v, normal)| {
+            let at = v.map(|c| f64::from(c * frequency));
+            let offset = noise.get([at.x, at.y, at.z]) as f32 * amplitude;
+            *v += normal * offset;
+        });
+}
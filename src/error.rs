@@ -0,0 +1,58 @@
+use std::fmt;
+use thiserror::Error;
+
+/// Why growing a fractal failed, for callers that need to tell bad input from a fractal that
+/// couldn't grow.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum FractulateError {
+    /// The mesh has no triangles at all.
+    #[error("the {0} has no triangles")]
+    EmptyInput(MeshRole),
+    /// Children were asked for but the mesh has no area to place them on.
+    #[error("{0} has zero surface area, cannot place growths")]
+    ZeroArea(MeshRole),
+    /// The mesh has area, but none of it passes the normal filter, is in the region, or is marked
+    /// in the grow mask.
+    #[error("no triangles of the {mesh} {reason}")]
+    NoEligibleTriangles {
+        mesh: MeshRole,
+        /// What the triangles would have needed to do, such as "pass the normal filter".
+        reason: &'static str,
+    },
+    #[error("input is not a valid STL")]
+    InvalidStl(#[source] std::io::Error),
+    #[error("triangle {triangle} of the {mesh} has a NaN or infinite coordinate")]
+    NonFiniteVertex { mesh: MeshRole, triangle: usize },
+    /// Several child meshes were given without a weight each, with a negative or non-finite
+    /// one, or with none above zero.
+    #[error(
+        "expected a non-negative weight for each of the {children} child meshes, with some above \
+         zero"
+    )]
+    ChildWeights { children: usize },
+    /// Everything was filtered out, leaving nothing to write.
+    #[error("the result has no triangles")]
+    EmptyResult,
+    /// Anything else, such as a file in another format that couldn't be read.
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+/// Which mesh a [`FractulateError`] is about.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MeshRole {
+    /// The mesh the fractal grows from.
+    Base,
+    /// The mesh copied onto it.
+    Child,
+}
+
+impl fmt::Display for MeshRole {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            MeshRole::Base => "input mesh",
+            MeshRole::Child => "child mesh",
+        })
+    }
+}
@@ -0,0 +1,28 @@
+//! Comma separated values, one triangle per row as the nine coordinates of its vertices. Write
+//! only, for loading into spreadsheets and scripts rather than other mesh tools.
+
+use anyhow::Result;
+use std::io::{BufWriter, Write};
+
+use crate::Triangle;
+
+/// Names of the columns, the first row written.
+pub const HEADER: &str = "x0,y0,z0,x1,y1,z1,x2,y2,z2";
+
+/// Write `triangles` with a header row, without needing them all in memory at once.
+pub fn write_iter<W: Write>(
+    writer: &mut W,
+    triangles: impl IntoIterator<Item = Triangle>,
+) -> Result<()> {
+    let mut writer = BufWriter::new(writer);
+    writeln!(writer, "{HEADER}")?;
+    for [a, b, c] in triangles {
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{},{},{}",
+            a.x, a.y, a.z, b.x, b.y, b.z, c.x, c.y, c.z
+        )?;
+    }
+    writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,238 @@
+//! Reading and writing meshes in the file formats fractulate understands.
+
+pub mod csv;
+pub mod obj;
+pub mod ply;
+pub mod stl;
+
+use anyhow::{bail, ensure, Result};
+use nalgebra::Vector3;
+use std::io::Write;
+
+use crate::{split_creases, weld, CompactMesh, IndexedMesh, Mesh, Triangle};
+
+/// A color, as red, green and blue.
+pub type Rgb = [u8; 3];
+
+/// A mesh file format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    Stl,
+    Obj,
+    /// Output only.
+    Ply,
+}
+
+impl Format {
+    /// Guess the format of a file from its contents.
+    ///
+    /// Binary STL has no magic number so anything that doesn't look like OBJ is assumed to be STL.
+    pub fn sniff(bytes: &[u8]) -> Self {
+        if bytes.starts_with(b"solid") {
+            return Format::Stl;
+        }
+        if obj::looks_like_obj(bytes) {
+            return Format::Obj;
+        }
+        Format::Stl
+    }
+}
+
+/// Parse a triangle mesh from the contents of a file.
+pub fn read(bytes: &[u8], format: Format) -> Result<Mesh> {
+    match format {
+        Format::Stl => Ok(stl::read(bytes)?),
+        Format::Obj => obj::read(bytes),
+        Format::Ply => bail!("reading PLY isn't supported"),
+    }
+}
+
+/// How far a polygon's vertices can stray from its plane, or bend its sides the wrong way, before
+/// it's rejected, relative to its size.
+const POLYGON_TOLERANCE: f32 = 1e-4;
+
+/// Split a convex polygon into triangles fanning out from its first vertex, in the same winding
+/// so their normals agree with the polygon's. For formats with faces of more than three sides.
+///
+/// Errors if the polygon isn't flat or isn't convex, as the fan would then cover the wrong area.
+/// Polygons with no area, all their vertices in a line, are fanned into degenerate triangles.
+pub(crate) fn triangulate(polygon: &[Vector3<f32>]) -> Result<Vec<Triangle>> {
+    ensure!(polygon.len() >= 3, "face needs at least 3 vertices");
+    let fan = (1..polygon.len() - 1)
+        .map(|k| [polygon[0], polygon[k], polygon[k + 1]])
+        .collect();
+    if polygon.len() == 3 {
+        return Ok(fan);
+    }
+    // Newell's method, which averages out a polygon that's a little bent
+    let normal: Vector3<f32> = (0..polygon.len())
+        .map(|i| polygon[i].cross(&polygon[(i + 1) % polygon.len()]))
+        .sum();
+    let Some(normal) = normal.try_normalize(0.0) else {
+        return Ok(fan);
+    };
+    let center = polygon.iter().sum::<Vector3<f32>>() / polygon.len() as f32;
+    let size = polygon
+        .iter()
+        .map(|v| (v - center).norm())
+        .fold(0.0, f32::max);
+    ensure!(
+        polygon
+            .iter()
+            .all(|v| (v - center).dot(&normal).abs() <= size * POLYGON_TOLERANCE),
+        "face of {} vertices isn't flat",
+        polygon.len()
+    );
+    // every corner turns the same way, and all the way round only once, so a star doesn't pass
+    let mut turned = 0.0;
+    for i in 0..polygon.len() {
+        let before = polygon[i] - polygon[(i + polygon.len() - 1) % polygon.len()];
+        let after = polygon[(i + 1) % polygon.len()] - polygon[i];
+        let sin = before.cross(&after).dot(&normal);
+        ensure!(
+            sin >= -before.norm() * after.norm() * POLYGON_TOLERANCE,
+            "face of {} vertices isn't convex",
+            polygon.len()
+        );
+        turned += sin.atan2(before.dot(&after));
+    }
+    ensure!(
+        turned < 3.0 * std::f32::consts::PI,
+        "face of {} vertices crosses itself",
+        polygon.len()
+    );
+    Ok(fan)
+}
+
+/// Settings for [`write`] that only apply to some formats.
+#[derive(Clone, Debug, Default)]
+pub struct WriteOptions {
+    /// Write STL as text rather than binary.
+    pub stl_ascii: bool,
+    /// Write PLY as text rather than binary.
+    pub ply_ascii: bool,
+    /// What STL starts with, see [`stl::write_iter`] and [`stl::write_ascii`]. Other formats
+    /// ignore it.
+    pub stl_header: Option<String>,
+    /// Formats that share vertices between faces merge vertices closer than this, see [`weld`].
+    pub weld_epsilon: f32,
+    /// Formats that share vertices between faces keep vertices apart where faces turn by more
+    /// than this many radians, see [`split_creases`].
+    pub weld_angle: Option<f32>,
+}
+
+impl WriteOptions {
+    /// `mesh` with its vertices shared as these options say.
+    pub fn welded(&self, mesh: &[Triangle]) -> IndexedMesh {
+        let welded = weld(mesh, self.weld_epsilon);
+        match self.weld_angle {
+            Some(angle) => split_creases(&welded, angle),
+            None => welded,
+        }
+    }
+}
+
+/// Serialize a triangle mesh.
+pub fn write<W: Write>(
+    writer: &mut W,
+    mesh: &[Triangle],
+    format: Format,
+    options: &WriteOptions,
+) -> Result<()> {
+    match format {
+        Format::Stl if options.stl_ascii => {
+            stl::write_ascii(writer, options.stl_header.as_deref(), mesh.iter().copied())
+        }
+        Format::Stl => stl::write(writer, options.stl_header.as_deref(), mesh),
+        Format::Obj => obj::write(writer, &options.welded(mesh)),
+        Format::Ply => ply::write(writer, &options.welded(mesh), None, options.ply_ascii),
+    }
+}
+
+/// Like [`write`], but for a [`CompactMesh`], expanding it as it's written. Formats that share
+/// vertices between faces use the compact mesh's own, rather than merging them again by
+/// `options.weld_epsilon` or splitting them by `options.weld_angle`.
+pub fn write_compact<W: Write>(
+    writer: &mut W,
+    mesh: &CompactMesh,
+    format: Format,
+    options: &WriteOptions,
+) -> Result<()> {
+    match format {
+        Format::Stl if options.stl_ascii => {
+            stl::write_ascii(writer, options.stl_header.as_deref(), mesh.triangles())
+        }
+        Format::Stl => stl::write_iter(
+            writer,
+            options.stl_header.as_deref(),
+            mesh.len(),
+            mesh.triangles(),
+        ),
+        Format::Obj => obj::write_parts(writer, mesh.vertices(), mesh.faces()),
+        Format::Ply => ply::write_parts(
+            writer,
+            mesh.vertices(),
+            mesh.faces(),
+            None,
+            options.ply_ascii,
+        ),
+    }
+}
+
+/// Like [`write`], but giving each triangle of `mesh` the matching color in `colors`. Only binary
+/// STL and PLY can hold colors.
+///
+/// PLY colors vertices rather than faces, so a vertex shared by faces of different colors takes
+/// the color of the last of them. Errors unless there's exactly one color per triangle.
+pub fn write_colored<W: Write>(
+    writer: &mut W,
+    mesh: &[Triangle],
+    colors: &[Rgb],
+    format: Format,
+    options: &WriteOptions,
+) -> Result<()> {
+    ensure!(
+        colors.len() == mesh.len(),
+        "{} colors for {} triangles",
+        colors.len(),
+        mesh.len()
+    );
+    match format {
+        Format::Stl if !options.stl_ascii => stl::write_colored_iter(
+            writer,
+            options.stl_header.as_deref(),
+            mesh.len(),
+            mesh.iter().copied().zip(colors.iter().copied()),
+        ),
+        Format::Ply => {
+            let welded = options.welded(mesh);
+            let mut vertex_colors = vec![[0; 3]; welded.vertices.len()];
+            for (face, color) in welded.faces.iter().zip(colors) {
+                for &i in face {
+                    vertex_colors[i] = *color;
+                }
+            }
+            ply::write(writer, &welded, Some(&vertex_colors), options.ply_ascii)
+        }
+        _ => bail!("only binary STL and PLY can hold colors"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_must_match_triangles() {
+        let mesh = crate::tetrahedron();
+        let options = WriteOptions::default();
+        for format in [Format::Stl, Format::Ply] {
+            let colors = vec![[255, 0, 0]; mesh.len()];
+            let mut out = Vec::new();
+            write_colored(&mut out, &mesh, &colors, format, &options).unwrap();
+            assert!(!out.is_empty());
+            let mut out = Vec::new();
+            assert!(write_colored(&mut out, &mesh, &colors[1..], format, &options).is_err());
+        }
+    }
+}
@@ -0,0 +1,246 @@
+//! Wavefront OBJ. Only vertex positions and faces are read, along with group and object names and
+//! texture coordinates if asked for with [`read_grouped`] or [`read_all`]; normals and materials
+//! are ignored.
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use nalgebra::{Vector2, Vector3};
+use std::io::{BufWriter, Write};
+use std::ops::Range;
+
+use super::triangulate;
+use crate::{IndexedMesh, Mesh, Triangle};
+
+/// Parse the faces of an OBJ file. Polygons are fan triangulated, and must be flat and convex.
+pub fn read(bytes: &[u8]) -> Result<Mesh> {
+    Ok(read_grouped(bytes)?.0)
+}
+
+/// A run of triangles named by a `g` or `o` statement.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Group {
+    pub name: String,
+    /// Indices of the triangles, in the mesh the group was read with.
+    pub triangles: Range<usize>,
+}
+
+/// Like [`read`], but also returning the groups the faces are in. A `g` statement puts the faces
+/// after it in each group it names, until the next `g` or `o`, and an `o` puts them in its object.
+/// A name can have several runs, and runs of different names can overlap. Runs with no faces are
+/// left out.
+pub fn read_grouped(bytes: &[u8]) -> Result<(Mesh, Vec<Group>)> {
+    let obj = read_all(bytes)?;
+    Ok((obj.mesh, obj.groups))
+}
+
+/// Everything [`read_all`] reads from an OBJ.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Obj {
+    pub mesh: Mesh,
+    /// As [`read_grouped`] returns them.
+    pub groups: Vec<Group>,
+    /// The `vt` texture coordinates of each triangle's corners, `None` for triangles of faces
+    /// that didn't give one for every vertex. The third coordinate, if any, is dropped.
+    pub uvs: Vec<Option<[Vector2<f32>; 3]>>,
+}
+
+/// Like [`read_grouped`], but also returning texture coordinates. They're fan triangulated along
+/// with the faces.
+pub fn read_all(bytes: &[u8]) -> Result<Obj> {
+    let text = std::str::from_utf8(bytes).context("OBJ is not valid utf-8")?;
+
+    let mut read = Parser::default();
+    for (i, line) in text.lines().enumerate() {
+        read.parse_line(line)
+            .with_context(|| format!("OBJ line {}", i + 1))?;
+    }
+    read.groups.close(read.triangles.len(), true);
+
+    Ok(Obj {
+        mesh: read.triangles.into(),
+        groups: read.groups.closed,
+        uvs: read.uvs,
+    })
+}
+
+/// What's been read of an OBJ so far.
+#[derive(Default)]
+struct Parser {
+    vertices: Vec<Vector3<f32>>,
+    texture_coords: Vec<Vector2<f32>>,
+    triangles: Vec<Triangle>,
+    uvs: Vec<Option<[Vector2<f32>; 3]>>,
+    groups: Groups,
+}
+
+/// Groups read so far, and those still taking faces with the index of their first triangle.
+#[derive(Default)]
+struct Groups {
+    object: Option<(String, usize)>,
+    groups: Vec<(String, usize)>,
+    closed: Vec<Group>,
+}
+
+impl Groups {
+    /// End the open groups at triangle `end`, and the object too if `object`.
+    fn close(&mut self, end: usize, object: bool) {
+        let object = self.object.take_if(|_| object);
+        for (name, start) in self.groups.drain(..).chain(object) {
+            if start < end {
+                self.closed.push(Group {
+                    name,
+                    triangles: start..end,
+                });
+            }
+        }
+    }
+}
+
+impl Parser {
+    fn parse_line(&mut self, line: &str) -> Result<()> {
+        let mut words = line.split_whitespace();
+        let groups = &mut self.groups;
+        let triangles = &mut self.triangles;
+        match words.next() {
+            Some("g") => {
+                groups.close(triangles.len(), false);
+                groups.groups = words
+                    .map(|name| (name.to_string(), triangles.len()))
+                    .collect();
+            }
+            Some("o") => {
+                groups.close(triangles.len(), true);
+                let name = words.collect::<Vec<_>>().join(" ");
+                groups.object = (!name.is_empty()).then_some((name, triangles.len()));
+            }
+            Some("v") => {
+                let mut coord = || -> Result<f32> {
+                    let word = words
+                        .next()
+                        .ok_or_else(|| anyhow!("vertex needs 3 coordinates"))?;
+                    Ok(word.parse()?)
+                };
+                self.vertices
+                    .push(Vector3::new(coord()?, coord()?, coord()?));
+            }
+            Some("vt") => {
+                let u = words
+                    .next()
+                    .ok_or_else(|| anyhow!("texture coordinate needs a u"))?
+                    .parse()?;
+                let v = words.next().map(str::parse).transpose()?.unwrap_or(0.0);
+                self.texture_coords.push(Vector2::new(u, v));
+            }
+            Some("f") => {
+                let face = words
+                    .map(|word| vertex_index(word, self.vertices.len(), self.texture_coords.len()))
+                    .collect::<Result<Vec<_>>>()?;
+                let polygon: Vec<Vector3<f32>> =
+                    face.iter().map(|&(v, _)| self.vertices[v]).collect();
+                let fan = triangulate(&polygon)?;
+                let added = fan.len();
+                triangles.extend(fan);
+                // fanned out from the first corner, as triangulate does
+                let uvs: Option<Vec<Vector2<f32>>> = face
+                    .iter()
+                    .map(|&(_, uv)| uv.map(|i| self.texture_coords[i]))
+                    .collect();
+                self.uvs.extend(
+                    (1..=added).map(|k| uvs.as_ref().map(|uvs| [uvs[0], uvs[k], uvs[k + 1]])),
+                );
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Resolve a face vertex reference like `3`, `3/1/2`, `3//2` or `-1` to an index into the vertices
+/// read so far, and into the texture coordinates read so far if it gives one.
+fn vertex_index(
+    word: &str,
+    num_vertices: usize,
+    num_texture_coords: usize,
+) -> Result<(usize, Option<usize>)> {
+    let mut parts = word.split('/');
+    let vertex = resolve(parts.next().unwrap_or_default(), num_vertices, "vertex")?;
+    let texture_coord = match parts.next() {
+        Some(part) if !part.is_empty() => {
+            Some(resolve(part, num_texture_coords, "texture coordinate")?)
+        }
+        _ => None,
+    };
+    Ok((vertex, texture_coord))
+}
+
+/// Resolve one OBJ index, counting from 1, or back from the `len` read so far if negative. `what`
+/// is what errors call the thing it indexes.
+fn resolve(word: &str, len: usize, what: &str) -> Result<usize> {
+    let index: i64 = word.parse()?;
+    let ret = match index {
+        0 => bail!("{what} indices start at 1"),
+        i if i > 0 => i as usize - 1,
+        i => len
+            .checked_sub(i.unsigned_abs() as usize)
+            .ok_or_else(|| anyhow!("{what} index {index} out of range"))?,
+    };
+    ensure!(ret < len, "{what} index {index} out of range");
+    Ok(ret)
+}
+
+/// Write an OBJ with one `v` line per vertex and one `f` line per face.
+pub fn write<W: Write>(writer: &mut W, mesh: &IndexedMesh) -> Result<()> {
+    write_parts(
+        writer,
+        mesh.vertices.iter().copied(),
+        mesh.faces.iter().copied(),
+    )
+}
+
+/// Like [`write`], but taking the vertices and faces one at a time, so they needn't be in an
+/// [`IndexedMesh`].
+pub(crate) fn write_parts<W: Write>(
+    writer: &mut W,
+    vertices: impl Iterator<Item = Vector3<f32>>,
+    faces: impl Iterator<Item = [usize; 3]>,
+) -> Result<()> {
+    let mut writer = BufWriter::new(writer);
+    for v in vertices {
+        writeln!(writer, "v {} {} {}", v.x, v.y, v.z)?;
+    }
+    for face in faces {
+        // OBJ indices start at 1
+        let [a, b, c] = face.map(|i| i + 1);
+        writeln!(writer, "f {a} {b} {c}")?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Whether the first meaningful line of `bytes` is an OBJ statement.
+pub(crate) fn looks_like_obj(bytes: &[u8]) -> bool {
+    let Some(first) = bytes
+        .split(|&b| b == b'\n')
+        .map(|line| line.trim_ascii())
+        .find(|line| !line.is_empty())
+    else {
+        return false;
+    };
+    let keyword = first
+        .split(|b| b.is_ascii_whitespace())
+        .next()
+        .unwrap_or_default();
+    first.starts_with(b"#")
+        || [
+            &b"v"[..],
+            b"vt",
+            b"vn",
+            b"vp",
+            b"f",
+            b"o",
+            b"g",
+            b"s",
+            b"mtllib",
+            b"usemtl",
+        ]
+        .contains(&keyword)
+}
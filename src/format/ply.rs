@@ -0,0 +1,100 @@
+//! Stanford PLY. Only writing is supported.
+
+use anyhow::{anyhow, Result};
+use nalgebra::Vector3;
+use std::io::{BufWriter, Write};
+
+use super::Rgb;
+use crate::IndexedMesh;
+
+/// Write a PLY, either as text or as binary little endian, optionally with a color for each vertex.
+pub fn write<W: Write>(
+    writer: &mut W,
+    mesh: &IndexedMesh,
+    colors: Option<&[Rgb]>,
+    ascii: bool,
+) -> Result<()> {
+    write_parts(
+        writer,
+        mesh.vertices.iter().copied(),
+        mesh.faces.iter().copied(),
+        colors,
+        ascii,
+    )
+}
+
+/// Write `points` as a PLY with no faces, a point cloud.
+pub fn write_points<W: Write>(writer: &mut W, points: &[Vector3<f32>], ascii: bool) -> Result<()> {
+    write_parts(
+        writer,
+        points.iter().copied(),
+        std::iter::empty(),
+        None,
+        ascii,
+    )
+}
+
+/// Like [`write`], but taking the vertices and faces one at a time, so they needn't be in an
+/// [`IndexedMesh`].
+pub(crate) fn write_parts<W: Write>(
+    writer: &mut W,
+    vertices: impl ExactSizeIterator<Item = Vector3<f32>>,
+    faces: impl ExactSizeIterator<Item = [usize; 3]>,
+    colors: Option<&[Rgb]>,
+    ascii: bool,
+) -> Result<()> {
+    let mut writer = BufWriter::new(writer);
+    let format = if ascii {
+        "ascii"
+    } else {
+        "binary_little_endian"
+    };
+    writeln!(writer, "ply")?;
+    writeln!(writer, "format {format} 1.0")?;
+    writeln!(writer, "comment generated by fractulate")?;
+    writeln!(writer, "element vertex {}", vertices.len())?;
+    for axis in ["x", "y", "z"] {
+        writeln!(writer, "property float {axis}")?;
+    }
+    if colors.is_some() {
+        for channel in ["red", "green", "blue"] {
+            writeln!(writer, "property uchar {channel}")?;
+        }
+    }
+    writeln!(writer, "element face {}", faces.len())?;
+    writeln!(writer, "property list uchar int vertex_indices")?;
+    writeln!(writer, "end_header")?;
+
+    if ascii {
+        for (i, v) in vertices.enumerate() {
+            write!(writer, "{} {} {}", v.x, v.y, v.z)?;
+            if let Some([r, g, b]) = colors.map(|colors| colors[i]) {
+                write!(writer, " {r} {g} {b}")?;
+            }
+            writeln!(writer)?;
+        }
+        for [a, b, c] in faces {
+            writeln!(writer, "3 {a} {b} {c}")?;
+        }
+    } else {
+        for (i, v) in vertices.enumerate() {
+            for c in v.iter() {
+                writer.write_all(&c.to_le_bytes())?;
+            }
+            if let Some(colors) = colors {
+                writer.write_all(&colors[i])?;
+            }
+        }
+        for face in faces {
+            writer.write_all(&[3])?;
+            for i in face {
+                let i: i32 = i
+                    .try_into()
+                    .map_err(|_| anyhow!("too many vertices for PLY"))?;
+                writer.write_all(&i.to_le_bytes())?;
+            }
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,188 @@
+use anyhow::{anyhow, ensure, Result};
+use nalgebra::Vector3;
+use std::io::{BufWriter, Cursor, Read, Write};
+
+use super::Rgb;
+use crate::{get_normal, FractulateError, Mesh, Triangle};
+
+/// Parse an ascii or binary STL.
+pub fn read(bytes: &[u8]) -> Result<Mesh, FractulateError> {
+    let stl = stl_io::read_stl(&mut Cursor::new(bytes)).map_err(FractulateError::InvalidStl)?;
+
+    let mesh = stl
+        .faces
+        .iter()
+        .map(|face| {
+            face.vertices.map(|v| {
+                let ret: [f32; 3] = stl.vertices[v].into();
+                let ret: Vector3<f32> = ret.into();
+                ret
+            })
+        })
+        .collect();
+    Ok(mesh)
+}
+
+/// Bytes in a binary STL's header.
+pub const HEADER_LEN: usize = 80;
+
+/// Bytes in each triangle of a binary STL: a normal, three vertices, and two attribute bytes.
+const RECORD_LEN: usize = 50;
+
+/// Triangles to make room for up front, however many a binary STL claims to have, so a corrupt
+/// count can't ask for more memory than the file could fill.
+const MAX_RESERVE: usize = 1 << 20;
+
+/// Parse a binary STL a triangle at a time as it's read, rather than needing the whole file in
+/// memory first, along with its [`header`]. Pass a buffered `reader`.
+///
+/// Errors if the file ends before the triangles it says it has. Anything after them is ignored.
+pub fn read_binary<R: Read>(mut reader: R) -> Result<(Mesh, Option<String>), FractulateError> {
+    let mut start = [0; HEADER_LEN + 4];
+    reader
+        .read_exact(&mut start)
+        .map_err(FractulateError::InvalidStl)?;
+    let count = u32::from_le_bytes(start[HEADER_LEN..].try_into().unwrap());
+    let mut triangles = Vec::with_capacity((count as usize).min(MAX_RESERVE));
+    let mut record = [0; RECORD_LEN];
+    for _ in 0..count {
+        reader
+            .read_exact(&mut record)
+            .map_err(FractulateError::InvalidStl)?;
+        // skip the normal, it's worked out again from the vertices wherever it's needed
+        let c = |i: usize| f32::from_le_bytes(record[12 + 4 * i..][..4].try_into().unwrap());
+        triangles.push([0, 1, 2].map(|v| Vector3::new(c(3 * v), c(3 * v + 1), c(3 * v + 2))));
+    }
+    Ok((Mesh::new(triangles), header(&start[..HEADER_LEN])))
+}
+
+/// The text an STL starts with, the solid's name given after `solid` in an ascii STL or the
+/// header of a binary one. Binary headers are often binary junk themselves, so anything that
+/// isn't printable ascii is dropped. `None` if there isn't any.
+pub fn header(bytes: &[u8]) -> Option<String> {
+    let text: Vec<u8> = match bytes.strip_prefix(b"solid") {
+        Some(rest) => rest.iter().take_while(|&&b| b != b'\n').copied().collect(),
+        None => bytes.get(..HEADER_LEN)?.to_vec(),
+    };
+    let text: String = text
+        .iter()
+        .filter(|b| b.is_ascii_graphic() || **b == b' ')
+        .map(|&b| char::from(b))
+        .collect();
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Write a binary STL. `header` goes at the start, if given, see [`write_iter`].
+pub fn write<W: Write>(writer: &mut W, header: Option<&str>, mesh: &[Triangle]) -> Result<()> {
+    write_iter(writer, header, mesh.len(), mesh.iter().copied())
+}
+
+/// Write a binary STL of `count` triangles without needing them all in memory at once.
+///
+/// Binary STL starts with the number of triangles, so it has to be known up front. Errors if
+/// `triangles` doesn't yield exactly `count` triangles.
+///
+/// The 80 byte header holds as much of `header` as fits, or zeros without one. A leading `solid`
+/// is left out, since that would make readers take the file for ascii.
+pub fn write_iter<W: Write>(
+    writer: &mut W,
+    header: Option<&str>,
+    count: usize,
+    triangles: impl IntoIterator<Item = Triangle>,
+) -> Result<()> {
+    write_facets(
+        writer,
+        header,
+        count,
+        triangles.into_iter().map(|triangle| (triangle, 0)),
+    )
+}
+
+/// Like [`write_iter`], but with a color for each triangle. The color goes in the attribute bytes
+/// the way VisCAM and SolidView do it, 5 bits each of blue, green then red, with the top bit set to
+/// mark the color valid. Other readers ignore it.
+pub fn write_colored_iter<W: Write>(
+    writer: &mut W,
+    header: Option<&str>,
+    count: usize,
+    triangles: impl IntoIterator<Item = (Triangle, Rgb)>,
+) -> Result<()> {
+    let attribute = |[r, g, b]: Rgb| {
+        let [r, g, b] = [r, g, b].map(|c| u16::from(c >> 3));
+        0x8000 | r << 10 | g << 5 | b
+    };
+    write_facets(
+        writer,
+        header,
+        count,
+        triangles
+            .into_iter()
+            .map(|(triangle, color)| (triangle, attribute(color))),
+    )
+}
+
+/// Write a binary STL of `count` triangles, each with its attribute bytes.
+fn write_facets<W: Write>(
+    writer: &mut W,
+    header: Option<&str>,
+    count: usize,
+    triangles: impl IntoIterator<Item = (Triangle, u16)>,
+) -> Result<()> {
+    let mut writer = BufWriter::new(writer);
+    let header_count: u32 = count
+        .try_into()
+        .map_err(|_| anyhow!("{count} triangles is too many for binary STL"))?;
+    let mut header_bytes = [0; HEADER_LEN];
+    if let Some(header) = header {
+        let header = header.strip_prefix("solid").unwrap_or(header).trim_start();
+        let len = header.len().min(HEADER_LEN);
+        header_bytes[..len].copy_from_slice(&header.as_bytes()[..len]);
+    }
+    writer.write_all(&header_bytes)?;
+    writer.write_all(&header_count.to_le_bytes())?;
+
+    let mut written = 0;
+    for (triangle, attribute) in triangles {
+        ensure!(written < count, "more than the expected {count} triangles");
+        let normal = get_normal(&triangle);
+        for v in std::iter::once(&normal).chain(&triangle) {
+            for c in v.iter() {
+                writer.write_all(&c.to_le_bytes())?;
+            }
+        }
+        writer.write_all(&attribute.to_le_bytes())?;
+        written += 1;
+    }
+    ensure!(
+        written == count,
+        "expected {count} triangles but only got {written}"
+    );
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write an ascii STL, naming the solid `header` or else `fractulate`. Line breaks in the name
+/// become spaces.
+pub fn write_ascii<W: Write>(
+    writer: &mut W,
+    header: Option<&str>,
+    triangles: impl IntoIterator<Item = Triangle>,
+) -> Result<()> {
+    let mut writer = BufWriter::new(writer);
+    let name = header.unwrap_or("fractulate").replace(['\n', '\r'], " ");
+    writeln!(writer, "solid {name}")?;
+    for triangle in triangles {
+        let [nx, ny, nz] = get_normal(&triangle).into();
+        writeln!(writer, "  facet normal {nx} {ny} {nz}")?;
+        writeln!(writer, "    outer loop")?;
+        for v in triangle {
+            writeln!(writer, "      vertex {} {} {}", v.x, v.y, v.z)?;
+        }
+        writeln!(writer, "    endloop")?;
+        writeln!(writer, "  endfacet")?;
+    }
+    writeln!(writer, "endsolid {name}")?;
+    writer.flush()?;
+    Ok(())
+}
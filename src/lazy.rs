@@ -0,0 +1,231 @@
+use rand::Rng;
+use std::collections::VecDeque;
+
+use crate::{
+    grow_child, max_instances, next_child, out_of_budget, place_point, Anchors, ChildCursor,
+    ChildRng, FractulateError, GenerationAreas, GrowthParams, Instance, Mesh, Origin, Siblings,
+    Triangle,
+};
+
+/// Like [`grow`](crate::grow), but yielding the triangles one at a time as they are placed rather
+/// than collecting the whole fractal. Yields the same triangles in the same order as
+/// [`instantiate`](crate::instantiate) given the same `rng`.
+///
+/// Memory stays proportional to the depth when growing depth first. Breadth first has to remember
+/// a whole generation at a time, and under [`GrowthParams::grow_on_previous`] the whole fractal's
+/// instances are laid out first, since each generation draws from all of the one before.
+///
+/// Errors the same way as [`instances`](crate::instances).
+pub fn grow_iter<'a, R: Rng + 'a>(
+    base: &'a Mesh,
+    child: &'a Mesh,
+    params: &'a GrowthParams,
+    mut rng: R,
+) -> Result<impl Iterator<Item = Triangle> + 'a, FractulateError> {
+    let children = std::slice::from_ref(child);
+    let root = Instance::root(params);
+    let planned = match params.grow_on_previous {
+        true => {
+            let mut planned = crate::instances_with(base, children, params, &mut rng)?.into_iter();
+            // the root is yielded first either way
+            planned.next();
+            Some(planned)
+        }
+        false => None,
+    };
+    let anchors = Anchors::new(base, children, params)?;
+    let mut pending = VecDeque::new();
+    let mut areas = GenerationAreas::default();
+    if let (Some(anchors), None) = (&anchors, &planned) {
+        areas.add(&anchors.surfaces(base, children), &root);
+        pending.push_back(Pending {
+            parent: root,
+            index: 0,
+            rng: None,
+            next: ChildCursor::default(),
+            siblings: Siblings::default(),
+        });
+    }
+    Ok(Growth {
+        base,
+        children,
+        params,
+        anchors,
+        rng,
+        pending,
+        placed: 1,
+        max_instances: max_instances(base, children, params),
+        current: root,
+        emitted: 0,
+        areas,
+        planned,
+    })
+}
+
+/// An instance whose children are still being grown.
+struct Pending {
+    parent: Instance,
+    /// Index of `parent` in the order the instances were placed.
+    index: usize,
+    /// What the children are seeded from, `None` for the root which uses the caller's generator.
+    rng: Option<ChildRng>,
+    /// Where [`next_child`] carries on from.
+    next: ChildCursor,
+    /// The children grown so far, to keep the next clear of.
+    siblings: Siblings,
+}
+
+struct Growth<'a, R> {
+    base: &'a Mesh,
+    children: &'a [Mesh],
+    params: &'a GrowthParams,
+    anchors: Option<Anchors>,
+    rng: R,
+    /// Growing depth first this is a stack of the current instance's ancestors, breadth first a
+    /// queue in the order they were placed.
+    pending: VecDeque<Pending>,
+    /// Instances placed so far, counting the root.
+    placed: usize,
+    max_instances: usize,
+    /// The instance whose triangles are being yielded.
+    current: Instance,
+    /// Triangles of `current` yielded so far.
+    emitted: usize,
+    areas: GenerationAreas,
+    /// Instances laid out up front, to yield rather than grow.
+    planned: Option<std::vec::IntoIter<Instance>>,
+}
+
+impl<R: Rng> Growth<'_, R> {
+    /// Place the next instance, or `None` when the fractal is done.
+    fn next_instance(&mut self) -> Option<Instance> {
+        if let Some(planned) = &mut self.planned {
+            return planned.next();
+        }
+        let surfaces = self.anchors.as_ref()?.surfaces(self.base, self.children);
+        let depth_first = !self.params.breadth_first();
+        while !out_of_budget(self.params, self.placed, self.max_instances) {
+            let pending = if depth_first {
+                self.pending.back_mut()
+            } else {
+                self.pending.front_mut()
+            }?;
+            if !depth_first && self.areas.settled(self.params, pending.parent.generation) {
+                return None;
+            }
+            let sampler = surfaces.of(&pending.parent).sampler;
+            let generation = pending.parent.generation;
+            let next = match &mut pending.rng {
+                Some(rng) => next_child(rng, sampler, self.params, generation, &mut pending.next),
+                None => next_child(
+                    &mut self.rng,
+                    sampler,
+                    self.params,
+                    generation,
+                    &mut pending.next,
+                ),
+            };
+            let parent = pending.parent;
+            let origin = pending.index;
+            let Some((mut rng, anchor, inward)) = next else {
+                if depth_first {
+                    self.pending.pop_back();
+                } else {
+                    self.pending.pop_front();
+                }
+                continue;
+            };
+            let Some(child) = grow_child(
+                &mut rng,
+                &surfaces,
+                self.params,
+                &parent,
+                Origin {
+                    parent: origin,
+                    triangle: anchor,
+                },
+                inward,
+                &mut pending.siblings,
+            ) else {
+                continue;
+            };
+            let index = self.placed;
+            self.placed += 1;
+            self.areas.add(&surfaces, &child);
+            if child.generation < child.max_generation {
+                self.pending.push_back(Pending {
+                    parent: child,
+                    index,
+                    rng: Some(rng),
+                    next: ChildCursor::default(),
+                    siblings: Siblings::default(),
+                });
+            }
+            return Some(child);
+        }
+        None
+    }
+}
+
+impl<R: Rng> Iterator for Growth<'_, R> {
+    type Item = Triangle;
+
+    fn next(&mut self) -> Option<Triangle> {
+        loop {
+            let mesh = match self.current.generation {
+                0 => self.base,
+                _ => &self.children[self.current.child_mesh],
+            };
+            if let Some(triangle) = mesh.get(self.emitted) {
+                self.emitted += 1;
+                return Some(triangle.map(|v| place_point(&self.current.transform, v)));
+            }
+            self.current = self.next_instance()?;
+            self.emitted = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{grow, BranchDepth, Placement, Traversal};
+    use rand::SeedableRng;
+
+    #[test]
+    fn matches_grow() {
+        let tetrahedron = crate::tetrahedron();
+        for (traversal, grow_on_previous) in [
+            (Traversal::DepthFirst, false),
+            (Traversal::BreadthFirst, false),
+            (Traversal::DepthFirst, true),
+        ] {
+            for (growth_probability, max_triangles, avoid_overlap, branch_depth) in [
+                (None, None, false, None),
+                (Some(0.6), Some(200), false, None),
+                (None, None, true, None),
+                (None, None, false, Some(BranchDepth::Uniform)),
+            ] {
+                let params = GrowthParams {
+                    depth: 3,
+                    num_children: 3,
+                    growth_probability,
+                    max_triangles,
+                    avoid_overlap,
+                    branch_depth,
+                    placement: Placement::Random,
+                    rotate_children: true,
+                    traversal,
+                    grow_on_previous,
+                    ..Default::default()
+                };
+                let rng = || ChildRng::seed_from_u64(3);
+                let grown = grow(&tetrahedron, &tetrahedron, &params, &mut rng()).unwrap();
+                let lazy: Vec<Triangle> = grow_iter(&tetrahedron, &tetrahedron, &params, rng())
+                    .unwrap()
+                    .collect();
+                assert_eq!(grown.to_vec(), lazy);
+            }
+        }
+    }
+}
@@ -0,0 +1,2936 @@
+//! Grow fractals out of triangle meshes by recursively placing scaled copies of a mesh onto its own
+//! surface.
+
+mod compact;
+mod decimate;
+mod dimension;
+mod displace;
+mod error;
+pub mod format;
+mod lazy;
+mod merge;
+mod mesh;
+mod occlusion;
+mod orient;
+mod preview;
+mod prune;
+mod sampler;
+mod smooth;
+mod stats;
+mod weld;
+mod wireframe;
+
+pub use compact::CompactMesh;
+pub use decimate::decimate;
+pub use dimension::BoxCounter;
+pub use displace::displace;
+pub use error::{FractulateError, MeshRole};
+pub use lazy::grow_iter;
+pub use merge::merge_coplanar;
+pub use mesh::{
+    apply_transforms, bounding_box, bounding_sphere, fit_scaling, Mesh, Sphere, Triangle,
+};
+pub use occlusion::ambient_occlusion;
+pub use orient::orient;
+pub use preview::Preview;
+pub use prune::prune_hidden;
+pub use sampler::{Search, WeightedSampler, DEFAULT_SAMPLE_TOLERANCE};
+pub use smooth::smooth;
+pub use stats::{AreaBin, AreaHistogram, Stats};
+pub use weld::{index, split_creases, weld, IndexedMesh, Watertightness};
+pub use wireframe::wireframe;
+
+use nalgebra::{Matrix4, Vector2, Vector3};
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Instant;
+
+/// Scalar that instance transforms are composed in. A copy deep in the fractal is placed by the
+/// product of every transform above it, and the `f64` feature keeps the error from piling up.
+#[cfg(not(feature = "f64"))]
+pub type Real = f32;
+/// Scalar that instance transforms are composed in. A copy deep in the fractal is placed by the
+/// product of every transform above it, and the `f64` feature keeps the error from piling up.
+#[cfg(feature = "f64")]
+pub type Real = f64;
+
+/// Each child gets its own generator, seeded from its parent's, so a child's descendants don't
+/// depend on how many random numbers its siblings' descendants used.
+type ChildRng = rand_xoshiro::Xoshiro256StarStar;
+
+/// Knobs controlling the shape of the fractal.
+///
+/// Can be read from and written to files with serde, where any field left out takes its default.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GrowthParams {
+    /// Number of generations to grow. 0 grows nothing.
+    pub depth: usize,
+    /// Cut branches short at random, rather than growing every branch all the way to `depth`.
+    pub branch_depth: Option<BranchDepth>,
+    /// Children grown per generation. 0 grows nothing.
+    pub num_children: usize,
+    /// Per generation overrides for `num_children`. Copies in generation `g` each grow the `g`th
+    /// number of children, so the base grows the first, and generations past the end of the list
+    /// use the last one. Ignored when empty.
+    pub children_schedule: Vec<usize>,
+    /// Instead of growing `num_children` children, give every triangle of every copy this
+    /// probability of growing one, independently of the others. Must be between 0 and 1.
+    pub growth_probability: Option<f64>,
+    /// Size of each child relative to its parent, 1.0 growing children the same size as the
+    /// parent. Per axis of the child as it's placed on its triangle: z is along the triangle's
+    /// normal, x along the triangle's first edge and y across the triangle.
+    pub child_scale: Vector3<f32>,
+    /// Multiply `child_scale` by this again for each generation after the first, so children in
+    /// generation `g` are `child_scale * scale_ratio^(g - 1)` the size of their parent. 1.0, the
+    /// default, scales every generation the same. Ignored along with `child_scale` when
+    /// `scale_schedule` isn't empty.
+    pub scale_ratio: f32,
+    /// Per generation overrides for `child_scale`. Children in generation `g` use the `g - 1`th
+    /// scale, and generations past the end of the list use the last one. Ignored when empty.
+    pub scale_schedule: Vec<f32>,
+    /// Draw each child's scale uniformly from this range instead, the same along every axis.
+    /// Overrides `child_scale` and `scale_schedule`.
+    pub scale_range: Option<(f32, f32)>,
+    /// Scale each child to this times the square root of its triangle's area instead, the same
+    /// along every axis, so children match the size of what they grow on rather than all being
+    /// one size. The area is in the parent's frame, so the scale is still relative to the parent.
+    /// Overrides `child_scale`, `scale_ratio` and `scale_schedule`, but not `scale_range`.
+    pub scale_to_area: Option<f32>,
+    /// Multiply each child's scale by its own random factor between `1 - scale_noise` and
+    /// `1 + scale_noise`, on top of whichever of the options above sets it. Varies children around
+    /// the size `scale_ratio` decays to rather than changing the decay, though as sizes are
+    /// relative to the parent the variation compounds down each branch. Must be at least 0 and
+    /// less than 1, 0 varying nothing.
+    pub scale_noise: f32,
+    /// Stop growing children once the output would exceed this many triangles. The base mesh is
+    /// always output in full, even if it alone exceeds the budget.
+    pub max_triangles: Option<usize>,
+    /// Stop growing children once this time has passed, keeping what has grown so far. Left out
+    /// of files, as an instant is only meaningful while the program runs.
+    #[serde(skip)]
+    pub deadline: Option<Instant>,
+    /// Order children are grown in, which decides who misses out when `max_triangles` runs out.
+    pub traversal: Traversal,
+    /// Stop growing once a generation adds less than this fraction of the area grown so far,
+    /// counting its own, to grow until the fractal fills out rather than to a set depth. Still
+    /// stops at `depth`. Grows breadth first whatever `traversal` says, so each generation is
+    /// whole when it's measured. Copies stretched unevenly are measured as if scaled evenly by as
+    /// much in volume.
+    pub area_epsilon: Option<f32>,
+    /// Where on its triangle each child is placed.
+    pub placement: Placement,
+    /// How close, in a straight line, children of the same parent may be placed to each other
+    /// under [`Placement::Poisson`]. Measured in the fractal's units, between where each is placed
+    /// on the surface before any `lift`. Ignored by other placements. A child still too close
+    /// after [`POISSON_RETRIES`] throws is left out.
+    pub min_distance: f32,
+    /// Move each child from where `placement` puts it by a random distance up to this in a random
+    /// direction across its triangle's plane, in units of the square root of the triangle's area.
+    /// Keeps children near their centroids without lining them up exactly. 0 moves nothing.
+    pub jitter: f32,
+    /// Which way each child points.
+    pub growth_mode: GrowthMode,
+    /// Whether children point the way `growth_mode` says, the opposite way, or both.
+    pub direction: GrowthDirection,
+    /// Turn each child about the direction it points so its x axis is as close to this unit vector
+    /// as it can be, rather than following its triangle's first edge, so children line up with
+    /// each other. Children pointing along it keep the edge's orientation. Like
+    /// [`NormalFilter::direction`], it's in each copy's own frame.
+    pub orient_up: Option<Vector3<f32>>,
+    /// Turn each child the way `orient_up` does, towards the direction given for its triangle by
+    /// index into whichever mesh they grow on, like `grow_mask`, such as each triangle's
+    /// [`uv_tangent`]. Zero, or a triangle past the end, leaves it as it was. Left out of files,
+    /// as it belongs to a particular mesh.
+    #[serde(skip)]
+    pub tangents: Option<Vec<Vector3<f32>>>,
+    /// Spin each child by a random angle about its triangle's normal.
+    pub rotate_children: bool,
+    /// Tilt each child away from the direction it points by a random amount, so it points in a
+    /// direction uniformly distributed over the cone with this half angle in degrees. 0 tilts
+    /// nothing, and 90 scatters children over the whole hemisphere. Lifted along the tilted
+    /// direction, and spun about it with `rotate_children`.
+    pub spread_angle: f32,
+    /// Distance to raise each child off its triangle along the normal, in the units of the base
+    /// mesh. Like the children themselves, this shrinks with each generation.
+    pub lift: f32,
+    /// Only grow children on triangles facing this way.
+    pub normal_filter: Option<NormalFilter>,
+    /// Only grow children on triangles with their centroid in this box.
+    pub region: Option<Region>,
+    /// Only grow children on the triangles marked `true`, by index into whichever mesh they grow
+    /// on, to pick out parts of a mesh by something other than where they are, like an OBJ
+    /// [`Group`](format::obj::Group). Triangles past the end aren't marked. Left out of files, as
+    /// it belongs to a particular mesh.
+    #[serde(skip)]
+    pub grow_mask: Option<Vec<bool>>,
+    /// How likely each triangle is to be chosen to grow a child.
+    pub selection_weight: SelectionWeight,
+    /// Favor triangles facing this way, on top of `selection_weight`.
+    pub bias: Option<Bias>,
+    /// Skip children whose triangle, where it ends up in the fractal, has less area than this, in
+    /// the units of the base mesh. Stops deep generations wasting triangles on specks.
+    pub min_triangle_area: f32,
+    /// Skip children whose bounding sphere, where they end up in the fractal, has a radius less
+    /// than this, in the units of the base mesh, so each branch grows until its detail gets this
+    /// fine rather than for the same number of generations. `depth` and `max_triangles` still cap
+    /// how far it goes.
+    pub min_feature_size: f32,
+    /// How near the end of a sampler's weights, as a share of their total, a pick of a triangle
+    /// or child mesh lands to count as having got there by rounding, see
+    /// [`WeightedSampler::with_tolerance`].
+    pub sample_tolerance: Real,
+    /// Keep each child's bounding sphere from intersecting those of its siblings. A child that
+    /// would overlap is moved to another randomly chosen triangle, up to [`OVERLAP_RETRIES`] times,
+    /// and left out if it still overlaps. Under `growth_probability` a child can only grow on its
+    /// own triangle, so one that overlaps is left out straight away.
+    pub avoid_overlap: bool,
+    /// What the children of children grow on.
+    pub grow_on: GrowOn,
+    /// Spread each generation's children over the triangles of the whole generation before, as
+    /// if its copies were one mesh, rather than growing a set number on each copy. Copies placed
+    /// bigger get more of them, and some copies none. Grows a generation at a time whatever the
+    /// traversal. Changes nothing under `growth_probability`, where every triangle already has
+    /// its own chance, or [`GrowOn::Base`], where every generation grows on the base.
+    pub grow_on_previous: bool,
+    /// How likely each of several child meshes is to be the one a child is a copy of, in
+    /// proportion, one per mesh given to [`instances_with`], none negative. Empty weighs them all
+    /// the same.
+    /// Ignored with only one child mesh, in which case nothing is drawn to choose it, so a seed
+    /// grows the same fractal it always did.
+    pub child_weights: Vec<f32>,
+    /// How each child's mesh is chosen from several given to [`instances_with`].
+    pub child_choice: ChildChoice,
+}
+
+/// What generations after the first grow on. The first generation always grows on the base mesh.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GrowOn {
+    /// Each child grows its own children on itself, so every generation grows on the triangles
+    /// the one before it added.
+    #[default]
+    Child,
+    /// Every generation grows straight on the base mesh, each smaller than the last.
+    Base,
+}
+
+/// How a child picks which of several child meshes it's a copy of.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChildChoice {
+    /// At random, as [`GrowthParams::child_weights`] says.
+    #[default]
+    Random,
+    /// By generation, cycling through the child meshes in order. Generation 1 is a copy of the
+    /// first, generation 2 of the second, and so on, starting over from the first after the last.
+    /// With two meshes, odd generations are copies of the first and even of the second, such as
+    /// trunks bearing leaves. Nothing is drawn, and the weights are ignored.
+    ByGeneration,
+}
+
+/// Restricts growth to triangles whose normal is close to a direction.
+///
+/// The direction is in the base mesh's own frame, and every copy of the base is filtered the same
+/// way, so deeper generations follow the orientation of the child they grow on.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NormalFilter {
+    /// Unit vector triangles should face.
+    pub direction: Vector3<f32>,
+    /// Triangles are only grown on if the dot product of their normal with `direction` is above
+    /// this. 0 allows anything within 90 degrees of `direction`.
+    pub min_cos: f32,
+}
+
+/// Restricts growth to triangles whose centroid is inside an axis aligned box, to grow on only
+/// part of a mesh. Like a [`NormalFilter`], the box is in each copy's own frame.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Region {
+    pub min: Vector3<f32>,
+    pub max: Vector3<f32>,
+}
+
+impl Region {
+    /// Whether `triangle`'s centroid is in the box, counting its surface.
+    pub fn contains(&self, triangle: &Triangle) -> bool {
+        let [a, b, c] = triangle;
+        let centroid = (a + b + c) / 3.0;
+        (0..3).all(|i| (self.min[i]..=self.max[i]).contains(&centroid[i]))
+    }
+}
+
+/// Makes triangles facing a direction likelier to grow children, without ruling any out the way a
+/// [`NormalFilter`] does. Like the filter, the direction is in each copy's own frame.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Bias {
+    /// Unit vector favored triangles face.
+    pub direction: Vector3<f32>,
+    /// Between 0 and 1. At 0 direction makes no difference, while at 1 a triangle facing away
+    /// from `direction` never grows a child and one facing sideways has half the chance of one
+    /// facing along it.
+    pub strength: f32,
+}
+
+impl Bias {
+    /// How much to multiply the weight of a triangle with `normal` by.
+    fn factor(&self, normal: &Vector3<f32>) -> f32 {
+        let alignment = (normal.dot(&self.direction) + 1.0) / 2.0;
+        1.0 - self.strength + self.strength * alignment
+    }
+}
+
+impl GrowthParams {
+    /// Children each copy in `generation` grows, unless growing by `growth_probability`.
+    pub fn children_of(&self, generation: usize) -> usize {
+        let schedule = &self.children_schedule;
+        match schedule.get(generation).or(schedule.last()) {
+            Some(&children) => children,
+            None => self.num_children,
+        }
+    }
+
+    /// Size of a child in `generation` relative to its parent, per axis as for `child_scale`.
+    pub fn scale_for(&self, generation: usize) -> Vector3<f32> {
+        let schedule = &self.scale_schedule;
+        match schedule
+            .get(generation.saturating_sub(1))
+            .or(schedule.last())
+        {
+            Some(&scale) => Vector3::repeat(scale),
+            None => self.child_scale * self.scale_ratio.powi(generation.saturating_sub(1) as i32),
+        }
+    }
+
+    /// Size of a copy in `generation` relative to the base mesh, per axis as for `child_scale`.
+    pub fn size_of(&self, generation: usize) -> Vector3<f32> {
+        (1..=generation).fold(Vector3::repeat(1.0), |size, g| {
+            size.component_mul(&self.scale_for(g))
+        })
+    }
+
+    /// Whether growing breadth first, as `area_epsilon` needs even if `traversal` says otherwise.
+    fn breadth_first(&self) -> bool {
+        self.traversal == Traversal::BreadthFirst || self.area_epsilon.is_some()
+    }
+}
+
+/// How many more generations grow beneath each child, drawn from the child's own generator as it's
+/// placed. A child never grows deeper than its parent's branch does, so each branch keeps the
+/// shortest depth drawn along it, and none grows past [`GrowthParams::depth`].
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BranchDepth {
+    /// Uniformly from none to as many as the parent's branch allows, so a child's branch is equally
+    /// likely to stop anywhere between it and where its parent's does.
+    Uniform,
+    /// From a Poisson distribution with this mean, which must be at least 0.
+    Poisson { mean: f32 },
+}
+
+impl BranchDepth {
+    /// Generations to grow beneath a child, at most `limit`.
+    fn sample(self, rng: &mut ChildRng, limit: usize) -> usize {
+        match self {
+            BranchDepth::Uniform => rng.gen_range(0..=limit),
+            BranchDepth::Poisson { mean } => {
+                // Knuth's method, multiplying uniform draws until they fall below e^-mean, is
+                // slow for large means but fractals are never very deep
+                let threshold = (-f64::from(mean)).exp();
+                let mut product: f64 = rng.gen();
+                let mut drawn = 0;
+                while product > threshold && drawn < limit {
+                    product *= rng.gen::<f64>();
+                    drawn += 1;
+                }
+                drawn
+            }
+        }
+    }
+}
+
+/// Which way children point.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GrowthMode {
+    /// Out of the surface, along the normal of the triangle they grow on.
+    #[default]
+    Surface,
+    /// Straight away from the center of what they grow on, bursting out like a ball.
+    Radial,
+}
+
+/// Which side of the surface children grow on, relative to where [`GrowthMode`] points them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GrowthDirection {
+    /// Out of the surface.
+    #[default]
+    Outward,
+    /// Into the surface, turned over about their x axis rather than reflected, so they still face
+    /// out of themselves.
+    Inward,
+    /// Both ways, each chosen triangle growing an outward child and an inward twin that mirrors
+    /// it, doubling the children.
+    Both,
+}
+
+impl GrowthDirection {
+    /// Children grown on each chosen triangle.
+    pub fn children_per_anchor(self) -> usize {
+        match self {
+            GrowthDirection::Both => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// Where on the chosen triangle a child is placed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Placement {
+    /// Dead center.
+    #[default]
+    Centroid,
+    /// Uniformly random over the triangle's surface.
+    Random,
+    /// Uniformly random, but no closer to a sibling than the minimum distance, spreading children
+    /// out more evenly than chance does. A child placed too close is thrown somewhere else, up to
+    /// 30 times, and left out if there's still no room.
+    Poisson,
+}
+
+/// How likely a triangle is to be chosen to grow a child. Degenerate triangles are never chosen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SelectionWeight {
+    /// In proportion to its area, spreading children evenly over the surface.
+    #[default]
+    Area,
+    /// In inverse proportion to its area, crowding children onto fine detail.
+    InverseArea,
+    /// Every triangle equally.
+    Uniform,
+}
+
+impl SelectionWeight {
+    /// Relative chance of choosing each of `triangles`. Degenerate triangles get none. Inverse
+    /// areas are taken relative to the smallest triangle's, so they run from one down rather than
+    /// overflowing on tiny triangles.
+    pub fn weights(self, triangles: &[Triangle]) -> Vec<f32> {
+        let areas = triangles.iter().map(|triangle| {
+            if is_degenerate(triangle) {
+                0.0
+            } else {
+                area(triangle)
+            }
+        });
+        match self {
+            SelectionWeight::Area => areas.collect(),
+            SelectionWeight::InverseArea => {
+                let areas: Vec<f32> = areas.collect();
+                let smallest = areas
+                    .iter()
+                    .copied()
+                    .filter(|&area| area > 0.0)
+                    .fold(f32::INFINITY, f32::min);
+                areas
+                    .into_iter()
+                    .map(|area| if area > 0.0 { smallest / area } else { 0.0 })
+                    .collect()
+            }
+            SelectionWeight::Uniform => areas
+                .map(|area| if area > 0.0 { 1.0 } else { 0.0 })
+                .collect(),
+        }
+    }
+}
+
+/// Order in which the fractal is grown.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum, Serialize, Deserialize)]
+pub enum Traversal {
+    /// Grow each child's descendants before its next sibling. Under a triangle budget, the first
+    /// branches are grown in full and later ones go without.
+    #[default]
+    #[value(name = "dfs")]
+    #[serde(rename = "dfs")]
+    DepthFirst,
+    /// Grow a whole generation before the next. Under a triangle budget, only the last generation
+    /// grown is incomplete.
+    #[value(name = "bfs")]
+    #[serde(rename = "bfs")]
+    BreadthFirst,
+}
+
+impl Default for GrowthParams {
+    fn default() -> Self {
+        Self {
+            depth: 2,
+            branch_depth: None,
+            num_children: 5,
+            children_schedule: Vec::new(),
+            growth_probability: None,
+            child_scale: Vector3::repeat(0.5),
+            scale_ratio: 1.0,
+            scale_schedule: Vec::new(),
+            scale_range: None,
+            scale_to_area: None,
+            scale_noise: 0.0,
+            max_triangles: None,
+            deadline: None,
+            traversal: Traversal::default(),
+            area_epsilon: None,
+            placement: Placement::default(),
+            min_distance: 0.0,
+            jitter: 0.0,
+            growth_mode: GrowthMode::default(),
+            direction: GrowthDirection::default(),
+            orient_up: None,
+            tangents: None,
+            rotate_children: false,
+            spread_angle: 0.0,
+            lift: 0.0,
+            normal_filter: None,
+            region: None,
+            grow_mask: None,
+            selection_weight: SelectionWeight::default(),
+            bias: None,
+            min_triangle_area: 0.0,
+            min_feature_size: 0.0,
+            sample_tolerance: DEFAULT_SAMPLE_TOLERANCE,
+            avoid_overlap: false,
+            grow_on: GrowOn::default(),
+            grow_on_previous: false,
+            child_weights: Vec::new(),
+            child_choice: ChildChoice::default(),
+        }
+    }
+}
+
+/// A copy of the base or child mesh placed somewhere in the fractal.
+#[derive(Clone, Copy, Debug)]
+pub struct Instance {
+    /// Moves the mesh into place.
+    pub transform: Matrix4<Real>,
+    /// The base mesh itself is generation 0, its children generation 1, and so on. Every
+    /// generation after 0 is a copy of the child mesh.
+    pub generation: usize,
+    /// Scale relative to the base mesh, per axis as for [`GrowthParams::child_scale`].
+    pub size: Vector3<f32>,
+    /// What this grew from, `None` for the base mesh.
+    pub origin: Option<Origin>,
+    /// The deepest generation this copy's branch grows to. Copies in it grow no children.
+    pub max_generation: usize,
+    /// Which of the child meshes this is a copy of, by its index among those given to
+    /// [`instances_with`]. Always 0 for the base mesh, and with only one child mesh.
+    pub child_mesh: usize,
+}
+
+/// Where in the fractal a child grew.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Origin {
+    /// Index of the parent among the instances, in the order they were placed.
+    pub parent: usize,
+    /// Index of the triangle the child grew on, in the mesh given by [`Surfaces::of`] the parent.
+    pub triangle: usize,
+}
+
+impl Instance {
+    /// The base mesh, untransformed.
+    fn root(params: &GrowthParams) -> Self {
+        Instance {
+            transform: Matrix4::identity(),
+            generation: 0,
+            size: Vector3::repeat(1.0),
+            origin: None,
+            max_generation: params.depth,
+            child_mesh: 0,
+        }
+    }
+}
+
+/// A mesh children can grow on, and a sampler choosing which of its triangles they grow on.
+#[derive(Clone, Copy, Debug)]
+pub struct Surface<'a> {
+    pub mesh: &'a [Triangle],
+    pub sampler: &'a WeightedSampler,
+    /// Center of mass of `mesh`, which [`GrowthMode::Radial`] points children away from.
+    pub center: Vector3<f32>,
+}
+
+/// Where each generation grows.
+#[derive(Clone, Copy, Debug)]
+pub struct Surfaces<'a> {
+    /// What the base mesh's children grow on, the base mesh itself.
+    pub base: Surface<'a>,
+    children: &'a [Mesh],
+    anchors: &'a Anchors,
+}
+
+impl<'a> Surfaces<'a> {
+    /// What the children of `parent` grow on. Later generations grow on the child mesh they're a
+    /// copy of, or the base, see [`GrowOn`].
+    pub fn of(&self, parent: &Instance) -> Surface<'a> {
+        match (parent.generation, &self.anchors.children) {
+            (0, _) | (_, None) => self.base,
+            (_, Some(children)) => {
+                let (sampler, center) = &children[parent.child_mesh];
+                Surface {
+                    mesh: &self.children[parent.child_mesh],
+                    sampler,
+                    center: *center,
+                }
+            }
+        }
+    }
+
+    /// Area of `instance`, if growing to an area epsilon.
+    fn area(&self, instance: &Instance) -> Option<f32> {
+        let mesh = match instance.generation {
+            0 => 0,
+            _ => 1 + instance.child_mesh,
+        };
+        let area = self.anchors.areas.get(mesh)?;
+        Some(area * area_scale(&instance.transform))
+    }
+
+    /// Bounding sphere of the `i`th child mesh, if children are to avoid overlapping their
+    /// siblings or be measured against a minimum feature size.
+    pub fn child_bounds(&self, i: usize) -> Option<Sphere> {
+        self.anchors.child_bounds.get(i).copied().flatten()
+    }
+}
+
+/// Grow a fractal of `child` meshes from `base`. The output is the base mesh followed by every
+/// generation of children. Pass the base as `child` to grow the base onto itself.
+pub fn grow<R: Rng + ?Sized>(
+    base: &Mesh,
+    child: &Mesh,
+    params: &GrowthParams,
+    rng: &mut R,
+) -> Result<Mesh, FractulateError> {
+    Ok(instantiate(
+        base,
+        child,
+        &instances(base, child, params, rng)?,
+    ))
+}
+
+/// Like [`grow`], but with each child a copy of one of `children`, chosen at random as
+/// `params.child_weights` says.
+pub fn grow_with<R: Rng + ?Sized>(
+    base: &Mesh,
+    children: &[Mesh],
+    params: &GrowthParams,
+    rng: &mut R,
+) -> Result<Mesh, FractulateError> {
+    Ok(instantiate_with(
+        base,
+        children,
+        &instances_with(base, children, params, rng)?,
+    ))
+}
+
+/// Grow a fractal of the mesh in `input`, the contents of an STL or OBJ file, onto itself and
+/// return it as binary STL. Seeded the same way as the command line tool, so the same `seed`
+/// grows the same fractal.
+///
+/// Does no I/O of its own, for places without files or standard streams, like a browser.
+pub fn grow_bytes(
+    input: &[u8],
+    params: &GrowthParams,
+    seed: u64,
+) -> Result<Vec<u8>, FractulateError> {
+    let base = match format::Format::sniff(input) {
+        format::Format::Stl => format::stl::read(input)?,
+        format => format::read(input, format)?,
+    };
+    let mesh = grow(&base, &base, params, &mut ChildRng::seed_from_u64(seed))?;
+    let mut ret = Vec::new();
+    format::stl::write(&mut ret, None, &mesh)?;
+    Ok(ret)
+}
+
+/// Lay out the fractal without generating any geometry. The first instance is always the base
+/// mesh itself, followed by its descendants in the order given by `params.traversal`. The
+/// traversal only changes the order, unless the triangle budget runs out.
+///
+/// This is cheap compared to generating the triangles, which can then be done all at once with
+/// [`instantiate`] or piecewise to avoid holding the whole fractal in memory.
+///
+/// Any generator will do for `rng`, including a `&mut dyn RngCore` picked at runtime. It chooses
+/// where children grow and seeds each child's own generator, which is always xoshiro256** so what
+/// a child grows into only depends on its seed.
+///
+/// Errors if the base is empty or either mesh has a coordinate that isn't finite, or if there
+/// would be children but what they grow on has no area to place them on.
+pub fn instances<R: Rng + ?Sized>(
+    base: &Mesh,
+    child: &Mesh,
+    params: &GrowthParams,
+    rng: &mut R,
+) -> Result<Vec<Instance>, FractulateError> {
+    instances_with(base, std::slice::from_ref(child), params, rng)
+}
+
+/// Like [`instances`], but with each child a copy of one of `children`, chosen at random as
+/// `params.child_weights` says. The choice is drawn from the child's own generator, after its
+/// triangle. Under [`ChildChoice::ByGeneration`] it's chosen by the child's generation instead.
+///
+/// The triangle budget is spent as if every child were the largest of `children`, so with
+/// children of different sizes the fractal can stop short of it.
+///
+/// Also errors if `children` is empty, or `params.child_weights` doesn't have a weight for each
+/// of several `children`, has one that's negative or not finite, or has none above zero.
+pub fn instances_with<R: Rng + ?Sized>(
+    base: &Mesh,
+    children: &[Mesh],
+    params: &GrowthParams,
+    rng: &mut R,
+) -> Result<Vec<Instance>, FractulateError> {
+    Ok(instances_with_fallbacks(base, children, params, rng)?.0)
+}
+
+/// Like [`instances_with`], but also returns how many of this fractal's picks of a triangle or
+/// child landed past the last from rounding, as [`WeightedSampler::fallbacks`] counts them.
+pub fn instances_with_fallbacks<R: Rng + ?Sized>(
+    base: &Mesh,
+    children: &[Mesh],
+    params: &GrowthParams,
+    rng: &mut R,
+) -> Result<(Vec<Instance>, usize), FractulateError> {
+    let root = Instance::root(params);
+    let Some(anchors) = Anchors::new(base, children, params)? else {
+        return Ok((vec![root], 0));
+    };
+    let surfaces = anchors.surfaces(base, children);
+    let max_instances = max_instances(base, children, params);
+    let mut ret = Vec::new();
+    // the count is exact but may be too big to allocate, in which case the vec grows as it fills
+    let _ =
+        ret.try_reserve_exact(unbounded_instance_count(base, children, params).min(max_instances));
+    ret.push(root);
+    match params.breadth_first() {
+        _ if params.grow_on_previous => {
+            growths_pooled(rng, &surfaces, params, max_instances, &mut ret)
+        }
+        false => growths(rng, &surfaces, params, 0, max_instances, &mut ret),
+        true => growths_breadth_first(rng, &surfaces, params, max_instances, &mut ret),
+    }
+    Ok((ret, anchors.fallbacks()))
+}
+
+/// The samplers behind [`Surfaces`], built once per fractal.
+#[derive(Debug)]
+struct Anchors {
+    base: WeightedSampler,
+    base_center: Vector3<f32>,
+    /// A sampler and center per child mesh, `None` when later generations grow on the base too,
+    /// or there are no later generations.
+    children: Option<Vec<(WeightedSampler, Vector3<f32>)>>,
+    /// Bounding sphere of each child mesh, empty unless avoiding overlap or a minimum feature
+    /// size.
+    child_bounds: Vec<Option<Sphere>>,
+    /// Chooses which child mesh each child is a copy of, `None` when there's only one.
+    chooser: Option<WeightedSampler>,
+    /// Area of the base then each child mesh, empty unless growing to an area epsilon.
+    areas: Vec<f32>,
+}
+
+impl Anchors {
+    /// Picks by any of the samplers that fell back from rounding.
+    fn fallbacks(&self) -> usize {
+        let children = self.children.iter().flatten().map(|(sampler, _)| sampler);
+        std::iter::once(&self.base)
+            .chain(children)
+            .chain(&self.chooser)
+            .map(WeightedSampler::fallbacks)
+            .sum()
+    }
+
+    /// `None` if the base has no children, in which case nothing needs sampling.
+    fn new(
+        base: &Mesh,
+        children: &[Mesh],
+        params: &GrowthParams,
+    ) -> Result<Option<Self>, FractulateError> {
+        check_mesh(base, MeshRole::Base)?;
+        let childless = match params.growth_probability {
+            Some(p) => p == 0.0,
+            None => params.children_of(0) == 0,
+        };
+        if params.depth == 0 || childless {
+            return Ok(None);
+        }
+        for child in children {
+            check_mesh(child, MeshRole::Child)?;
+        }
+        let chooser = match children.len() {
+            0 => return Err(FractulateError::EmptyInput(MeshRole::Child)),
+            1 => None,
+            _ if params.child_choice == ChildChoice::ByGeneration => None,
+            n if params.child_weights.is_empty() => WeightedSampler::new(vec![1.0; n]),
+            n if params.child_weights.len() == n
+                && params
+                    .child_weights
+                    .iter()
+                    .all(|weight| weight.is_finite() && *weight >= 0.0) =>
+            {
+                let chooser = WeightedSampler::new(params.child_weights.iter().copied());
+                Some(chooser.ok_or(FractulateError::ChildWeights { children: n })?)
+            }
+            n => return Err(FractulateError::ChildWeights { children: n }),
+        }
+        .map(|chooser| chooser.with_tolerance(params.sample_tolerance));
+        let center = |mesh: &Mesh| Stats::of(mesh).centroid().unwrap_or_default();
+        let base_center = center(base);
+        let base_mesh = base;
+        let base = anchor_sampler(base, params, MeshRole::Base)?;
+        let children_anchors = match params.grow_on {
+            GrowOn::Child if params.depth > 1 => Some(
+                children
+                    .iter()
+                    .map(|child| {
+                        Ok((
+                            anchor_sampler(child, params, MeshRole::Child)?,
+                            center(child),
+                        ))
+                    })
+                    .collect::<Result<_, FractulateError>>()?,
+            ),
+            _ => None,
+        };
+        let child_bounds = if params.avoid_overlap || params.min_feature_size > 0.0 {
+            children
+                .iter()
+                .map(|child| bounding_sphere(child))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let areas = match params.area_epsilon {
+            Some(_) => std::iter::once(base_mesh)
+                .chain(children)
+                .map(|mesh| mesh.area())
+                .collect(),
+            None => Vec::new(),
+        };
+        Ok(Some(Anchors {
+            base,
+            base_center,
+            children: children_anchors,
+            child_bounds,
+            chooser,
+            areas,
+        }))
+    }
+
+    fn surfaces<'a>(&'a self, base: &'a Mesh, children: &'a [Mesh]) -> Surfaces<'a> {
+        Surfaces {
+            base: Surface {
+                mesh: base,
+                sampler: &self.base,
+                center: self.base_center,
+            },
+            children,
+            anchors: self,
+        }
+    }
+}
+
+/// Copies of the base and children that fit in the triangle budget.
+fn max_instances(base: &Mesh, children: &[Mesh], params: &GrowthParams) -> usize {
+    // children are only ever grown whole, so the budget is a number of copies
+    params.max_triangles.map_or(usize::MAX, |budget| {
+        1 + budget.saturating_sub(base.len()) / largest(children).max(1)
+    })
+}
+
+/// Triangles in the largest of `children`.
+fn largest(children: &[Mesh]) -> usize {
+    children.iter().map(|child| child.len()).max().unwrap_or(0)
+}
+
+/// Instances placed between looking at the clock for `GrowthParams::deadline`.
+const DEADLINE_INTERVAL: usize = 256;
+
+/// Area grown in each generation so far, to tell when growth has settled under
+/// [`GrowthParams::area_epsilon`]. Stays empty without one.
+#[derive(Debug, Default)]
+struct GenerationAreas(Vec<f32>);
+
+impl GenerationAreas {
+    fn add(&mut self, surfaces: &Surfaces, instance: &Instance) {
+        let Some(area) = surfaces.area(instance) else {
+            return;
+        };
+        if self.0.len() <= instance.generation {
+            self.0.resize(instance.generation + 1, 0.0);
+        }
+        self.0[instance.generation] += area;
+    }
+
+    /// Whether to stop before growing the children of copies in `generation`, which must be
+    /// whole, as must every generation before it.
+    fn settled(&self, params: &GrowthParams, generation: usize) -> bool {
+        let Some(epsilon) = params.area_epsilon else {
+            return false;
+        };
+        let Some(grown) = self.0.get(..=generation) else {
+            return false;
+        };
+        generation > 0 && grown[generation] < epsilon * grown.iter().sum::<f32>()
+    }
+}
+
+/// Whether to stop growing with `placed` instances placed so far.
+fn out_of_budget(params: &GrowthParams, placed: usize, max_instances: usize) -> bool {
+    placed >= max_instances
+        || params.deadline.is_some_and(|deadline| {
+            placed.is_multiple_of(DEADLINE_INTERVAL) && Instant::now() >= deadline
+        })
+}
+
+/// Place copies of `base` and `child` in parallel. Generation 0 instances are copies of `base`,
+/// the rest copies of `child`. Copies placed by a reflection are wound the other way so they still
+/// face out.
+///
+/// Copies come out in the order of `instances`, however many threads place them, so the output is
+/// the same bit for bit on any machine.
+pub fn instantiate(base: &Mesh, child: &Mesh, instances: &[Instance]) -> Mesh {
+    instantiate_with(base, std::slice::from_ref(child), instances)
+}
+
+/// Like [`instantiate`], but with children copies of whichever of `children` they say.
+pub fn instantiate_with(base: &Mesh, children: &[Mesh], instances: &[Instance]) -> Mesh {
+    let mesh_of = |instance: &Instance| match instance.generation {
+        0 => base,
+        _ => &children[instance.child_mesh],
+    };
+    // each copy is written straight into its slot of the output rather than collected separately
+    // and then concatenated, which would briefly need twice the memory
+    let count = instances
+        .iter()
+        .map(|instance| mesh_of(instance).len())
+        .sum();
+    let mut triangles = vec![[Vector3::zeros(); 3]; count];
+    let mut slots = Vec::with_capacity(instances.len());
+    let mut rest = triangles.as_mut_slice();
+    for instance in instances {
+        let (slot, tail) = std::mem::take(&mut rest).split_at_mut(mesh_of(instance).len());
+        slots.push(slot);
+        rest = tail;
+    }
+    slots
+        .into_par_iter()
+        .zip(instances)
+        .for_each(|(slot, instance)| {
+            for (out, triangle) in slot.iter_mut().zip(mesh_of(instance).iter()) {
+                *out = triangle.map(|v| place_point(&instance.transform, v));
+            }
+            if reflects(&instance.transform) {
+                for triangle in slot.iter_mut() {
+                    triangle.swap(1, 2);
+                }
+            }
+        });
+    triangles.into()
+}
+
+/// Like [`instantiate`], but for meshes that share vertices between triangles, as made by
+/// [`index`]. Each vertex of each copy is only placed once, however many triangles share it, which
+/// for a closed mesh is about a third of the work. Copies don't share vertices with each other.
+///
+/// Expanding the result with [`IndexedMesh::triangles`] gives exactly what [`instantiate`] does
+/// for the expanded `base` and `child`.
+pub fn instantiate_indexed(
+    base: &IndexedMesh,
+    child: &IndexedMesh,
+    instances: &[Instance],
+) -> IndexedMesh {
+    instantiate_indexed_with(base, std::slice::from_ref(child), instances)
+}
+
+/// Like [`instantiate_indexed`], but with children copies of whichever of `children` they say.
+pub fn instantiate_indexed_with(
+    base: &IndexedMesh,
+    children: &[IndexedMesh],
+    instances: &[Instance],
+) -> IndexedMesh {
+    let mesh_of = |instance: &Instance| match instance.generation {
+        0 => base,
+        _ => &children[instance.child_mesh],
+    };
+    // as in instantiate, each copy fills its own slots of the output
+    let (vertex_count, face_count) = instances.iter().fold((0, 0), |(v, f), instance| {
+        let mesh = mesh_of(instance);
+        (v + mesh.vertices.len(), f + mesh.faces.len())
+    });
+    let mut ret = IndexedMesh {
+        vertices: vec![Vector3::zeros(); vertex_count],
+        faces: vec![[0; 3]; face_count],
+    };
+    let mut slots = Vec::with_capacity(instances.len());
+    let (mut vertices, mut faces) = (ret.vertices.as_mut_slice(), ret.faces.as_mut_slice());
+    let mut first_vertex = 0;
+    for instance in instances {
+        let mesh = mesh_of(instance);
+        let (vertex_slot, vertex_tail) =
+            std::mem::take(&mut vertices).split_at_mut(mesh.vertices.len());
+        let (face_slot, face_tail) = std::mem::take(&mut faces).split_at_mut(mesh.faces.len());
+        slots.push((vertex_slot, face_slot, first_vertex));
+        (vertices, faces) = (vertex_tail, face_tail);
+        first_vertex += mesh.vertices.len();
+    }
+    slots.into_par_iter().zip(instances).for_each(
+        |((vertex_slot, face_slot, first_vertex), instance)| {
+            let mesh = mesh_of(instance);
+            for (out, v) in vertex_slot.iter_mut().zip(&mesh.vertices) {
+                *out = place_point(&instance.transform, *v);
+            }
+            let reflected = reflects(&instance.transform);
+            for (out, face) in face_slot.iter_mut().zip(&mesh.faces) {
+                *out = face.map(|i| i + first_vertex);
+                if reflected {
+                    out.swap(1, 2);
+                }
+            }
+        },
+    );
+    ret
+}
+
+/// A plane through the origin, named by the two axes it contains.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Plane {
+    Xy,
+    Yz,
+    Zx,
+}
+
+impl Plane {
+    /// Reflection across the plane.
+    pub fn reflection(self) -> Matrix4<f32> {
+        let normal = match self {
+            Plane::Xy => Vector3::z(),
+            Plane::Yz => Vector3::x(),
+            Plane::Zx => Vector3::y(),
+        };
+        Matrix4::new_nonuniform_scaling(&(Vector3::repeat(1.0) - normal * 2.0))
+    }
+}
+
+/// A coordinate axis.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    /// Unit vector pointing along the axis.
+    pub fn unit(self) -> nalgebra::Unit<Vector3<f32>> {
+        match self {
+            Axis::X => Vector3::x_axis(),
+            Axis::Y => Vector3::y_axis(),
+            Axis::Z => Vector3::z_axis(),
+        }
+    }
+}
+
+/// Add a reflection across `plane` of every copy but the base, so what grew on the base comes out
+/// symmetric about the plane. The reflections go after all of `instances`, in the same order.
+///
+/// Reflecting turns triangles inside out, which [`instantiate`] undoes by reversing the winding of
+/// copies whose transform reflects.
+pub fn mirror_children(instances: &mut Vec<Instance>, plane: Plane) {
+    let reflection = plane.reflection().cast::<Real>();
+    let mirrored: Vec<Instance> = instances
+        .iter()
+        .filter(|instance| instance.generation > 0)
+        .map(|instance| Instance {
+            transform: reflection * instance.transform,
+            ..*instance
+        })
+        .collect();
+    instances.extend(mirrored);
+}
+
+/// Where each child in `instances` was placed on the copy it grew on, in order, with the base
+/// skipped for having grown on nothing. Children stand [`GrowthParams::lift`] off the point they
+/// were placed at, which is taken back off, so `lift` must be what they grew with, and they mustn't
+/// have been moved since, as [`explode`] does.
+pub fn anchor_points(instances: &[Instance], lift: f32) -> impl Iterator<Item = Vector3<f32>> + '_ {
+    instances.iter().filter_map(move |instance| {
+        let parent = &instances[instance.origin?.parent];
+        // the lift is along the child's z axis before it was scaled
+        let scale = instance.size.z / parent.size.z;
+        let below = match lift != 0.0 && scale.is_normal() {
+            true => Vector3::new(0.0, 0.0, -lift / scale),
+            false => Vector3::zeros(),
+        };
+        Some(place_point(&instance.transform, below))
+    })
+}
+
+/// Move each copy of the first generation outward, along with everything that grew on it, by
+/// `factor` times how far the center of the copy is from the center of the base. The base stays
+/// put, and nothing changes shape, so the branches come apart for seeing how they overlap.
+/// Centers are the centroids of the surfaces, or of the bounding boxes of those with no area.
+///
+/// Branches are told apart by following [`Instance::origin`] back, so `instances` must be as
+/// [`instances_with`] planned them from `base` and `children`.
+pub fn explode(instances: &mut [Instance], base: &Mesh, children: &[Mesh], factor: f32) {
+    let center = |mesh: &Mesh| {
+        let stats = Stats::of(mesh);
+        stats
+            .centroid()
+            .or(stats.bounds.map(|(min, max)| (min + max) / 2.0))
+            .unwrap_or_default()
+    };
+    let middle = center(base);
+    let child_centers: Vec<Vector3<f32>> = children.iter().map(center).collect();
+    // parents are placed before their children, so each branch's offset is known before it's
+    // needed
+    let mut offsets: Vec<Vector3<f32>> = Vec::with_capacity(instances.len());
+    for instance in instances.iter() {
+        let offset = match instance.origin {
+            None => Vector3::zeros(),
+            Some(_) if instance.generation == 1 => {
+                let at = place_point(&instance.transform, child_centers[instance.child_mesh]);
+                (at - middle) * factor
+            }
+            Some(origin) => offsets[origin.parent],
+        };
+        offsets.push(offset);
+    }
+    for (instance, offset) in instances.iter_mut().zip(offsets) {
+        instance.transform = Matrix4::new_translation(&offset.cast::<Real>()) * instance.transform;
+    }
+}
+
+/// Whether `transform` turns meshes inside out, so their triangles need winding the other way to
+/// keep facing out.
+fn reflects(transform: &Matrix4<Real>) -> bool {
+    transform.fixed_view::<3, 3>(0, 0).determinant() < 0.0
+}
+
+/// Number of triangles [`instantiate_with`] outputs for `instances`, given the triangles in
+/// each of the child meshes.
+pub fn instance_triangles(
+    base_triangles: usize,
+    child_triangles: &[usize],
+    instances: &[Instance],
+) -> usize {
+    instances
+        .iter()
+        .map(|instance| match instance.generation {
+            0 => base_triangles,
+            _ => child_triangles[instance.child_mesh],
+        })
+        .sum()
+}
+
+/// Generation of each triangle [`instantiate_with`] outputs for `instances`, in order, given the
+/// triangles in each of the child meshes.
+pub fn triangle_generations<'a>(
+    base_triangles: usize,
+    child_triangles: &'a [usize],
+    instances: &'a [Instance],
+) -> impl Iterator<Item = usize> + 'a {
+    instances.iter().flat_map(move |instance| {
+        let triangles = match instance.generation {
+            0 => base_triangles,
+            _ => child_triangles[instance.child_mesh],
+        };
+        std::iter::repeat_n(instance.generation, triangles)
+    })
+}
+
+/// Number of triangles [`grow`] would output for a base mesh of `base_triangles` triangles and a
+/// child mesh of `child_triangles` if there were no triangle budget. Saturates at `usize::MAX`.
+///
+/// Under `growth_probability` the count is random, so this is the expected count if every triangle
+/// can grow a child.
+pub fn unbounded_triangle_count(
+    base_triangles: usize,
+    child_triangles: usize,
+    params: &GrowthParams,
+) -> usize {
+    let Some(p) = params.growth_probability else {
+        let copies = unbounded_copies(params);
+        return (copies - 1)
+            .saturating_mul(child_triangles)
+            .saturating_add(base_triangles);
+    };
+    let surface_triangles = match params.grow_on {
+        GrowOn::Child => child_triangles,
+        GrowOn::Base => base_triangles,
+    };
+    let p = p * params.direction.children_per_anchor() as f64;
+    let first = p * base_triangles as f64;
+    let later = p * surface_triangles as f64;
+    let children: f64 = (0..params.depth)
+        .map(|g| first * later.powi(g as i32))
+        .sum();
+    // float to int casts saturate
+    (children * child_triangles as f64).round() as usize + base_triangles
+}
+
+/// Number of triangles [`grow`] will output for a base mesh of `base_triangles` triangles and a
+/// child mesh of `child_triangles`. Saturates at `usize::MAX`.
+pub fn triangle_count(
+    base_triangles: usize,
+    child_triangles: usize,
+    params: &GrowthParams,
+) -> usize {
+    let unbounded = unbounded_triangle_count(base_triangles, child_triangles, params);
+    let Some(budget) = params.max_triangles else {
+        return unbounded;
+    };
+    if child_triangles == 0 || budget <= base_triangles {
+        return base_triangles;
+    }
+    // children are only ever grown whole, so the budget is effectively rounded down to a whole
+    // number of copies of the child
+    let children = (budget - base_triangles) / child_triangles * child_triangles;
+    unbounded.min(base_triangles + children)
+}
+
+/// Instances in the fractal if there were no triangle budget, a guess under `growth_probability`.
+fn unbounded_instance_count(base: &Mesh, children: &[Mesh], params: &GrowthParams) -> usize {
+    match params.growth_probability {
+        None => unbounded_copies(params),
+        Some(_) => {
+            let child = largest(children);
+            let children = unbounded_triangle_count(base.len(), child, params) - base.len();
+            1 + children / child.max(1)
+        }
+    }
+}
+
+/// Copies in the fractal, counting the base, when growing a fixed number of children with no
+/// triangle budget.
+fn unbounded_copies(params: &GrowthParams) -> usize {
+    let mut copies: usize = 0;
+    let mut generation_copies: usize = 1;
+    for generation in 0..=params.depth {
+        copies = copies.saturating_add(generation_copies);
+        generation_copies = generation_copies.saturating_mul(
+            params
+                .children_of(generation)
+                .saturating_mul(params.direction.children_per_anchor()),
+        );
+    }
+    copies
+}
+
+/// Place the descendants of `out[parent]`, appending them to `out`.
+///
+/// Stops once `out` holds `max_instances`, so earlier children are grown in full before later ones
+/// get any of the budget. Works down the branches on a stack of its own rather than recursing, so
+/// however deep they go only the budget limits them.
+pub fn growths<R: Rng + ?Sized>(
+    rng: &mut R,
+    surfaces: &Surfaces,
+    params: &GrowthParams,
+    parent: usize,
+    max_instances: usize,
+    out: &mut Vec<Instance>,
+) {
+    if out[parent].generation >= out[parent].max_generation {
+        return;
+    }
+    // the current instance's ancestors, each still growing children
+    let mut stack = vec![Branch {
+        parent,
+        rng: None,
+        next: ChildCursor::default(),
+        siblings: Siblings::default(),
+    }];
+    while let Some(branch) = stack.last_mut() {
+        let parent = out[branch.parent];
+        let sampler = surfaces.of(&parent).sampler;
+        let next = match &mut branch.rng {
+            Some(rng) => next_child(rng, sampler, params, parent.generation, &mut branch.next),
+            None => next_child(rng, sampler, params, parent.generation, &mut branch.next),
+        };
+        let Some((mut rng, anchor, inward)) = next else {
+            stack.pop();
+            continue;
+        };
+        if out_of_budget(params, out.len(), max_instances) {
+            return;
+        }
+        let origin = Origin {
+            parent: branch.parent,
+            triangle: anchor,
+        };
+        let Some(child) = grow_child(
+            &mut rng,
+            surfaces,
+            params,
+            &parent,
+            origin,
+            inward,
+            &mut branch.siblings,
+        ) else {
+            continue;
+        };
+        out.push(child);
+        if child.generation < child.max_generation {
+            stack.push(Branch {
+                parent: out.len() - 1,
+                rng: Some(rng),
+                next: ChildCursor::default(),
+                siblings: Siblings::default(),
+            });
+        }
+    }
+}
+
+/// An instance [`growths`] is still growing children of.
+struct Branch {
+    /// Index of the instance in `out`.
+    parent: usize,
+    /// What the children are seeded from, `None` for where [`growths`] started, which uses the
+    /// caller's generator.
+    rng: Option<ChildRng>,
+    /// Where [`next_child`] carries on from.
+    next: ChildCursor,
+    /// The children grown so far, to keep the next clear of.
+    siblings: Siblings,
+}
+
+/// Like [`growths`] from the root, but growing a whole generation before the next. Grows the same
+/// fractal as [`growths`] given the same `rng`, just in a different order, unless `max_instances`
+/// is reached first.
+///
+/// `out` must hold just the root.
+pub fn growths_breadth_first<R: Rng + ?Sized>(
+    rng: &mut R,
+    surfaces: &Surfaces,
+    params: &GrowthParams,
+    max_instances: usize,
+    out: &mut Vec<Instance>,
+) {
+    // indices of instances whose children are yet to be grown, each with the generator its
+    // children are seeded from
+    let mut pending = VecDeque::new();
+    let mut areas = GenerationAreas::default();
+    areas.add(surfaces, &out[0]);
+    if push_children(rng, surfaces, params, 0, max_instances, out, &mut pending) {
+        return;
+    }
+    let mut measured = 1;
+    while let Some((parent, mut rng)) = pending.pop_front() {
+        for instance in &out[measured..] {
+            areas.add(surfaces, instance);
+        }
+        measured = out.len();
+        if areas.settled(params, out[parent].generation) {
+            return;
+        }
+        if push_children(
+            &mut rng,
+            surfaces,
+            params,
+            parent,
+            max_instances,
+            out,
+            &mut pending,
+        ) {
+            return;
+        }
+    }
+}
+
+/// Like [`growths_breadth_first`], but under [`GrowthParams::grow_on_previous`]: each generation
+/// grows `children_of` its generation times as many children as it has copies still growing,
+/// each on a triangle drawn from all of theirs at once, by its weight scaled by the area its copy
+/// was placed at.
+///
+/// `out` must hold just the root.
+pub fn growths_pooled<R: Rng + ?Sized>(
+    rng: &mut R,
+    surfaces: &Surfaces,
+    params: &GrowthParams,
+    max_instances: usize,
+    out: &mut Vec<Instance>,
+) {
+    if params.growth_probability.is_some() || params.grow_on == GrowOn::Base {
+        growths_breadth_first(rng, surfaces, params, max_instances, out);
+        return;
+    }
+    let mut areas = GenerationAreas::default();
+    let mut generation = 0..out.len();
+    while !generation.is_empty() {
+        for instance in &out[generation.clone()] {
+            areas.add(surfaces, instance);
+        }
+        let depth = out[generation.start].generation;
+        if areas.settled(params, depth) {
+            return;
+        }
+        let parents = out[generation.clone()].to_vec();
+        let growing = |parent: &Instance| parent.generation < parent.max_generation;
+        let weights = parents.iter().map(|parent| match growing(parent) {
+            true => {
+                let total: f32 = nalgebra::convert(surfaces.of(parent).sampler.total());
+                total * area_scale(&parent.transform)
+            }
+            false => 0.0,
+        });
+        let Some(pool) = WeightedSampler::new(weights) else {
+            return;
+        };
+        let pool = pool.with_tolerance(params.sample_tolerance);
+        let draws = parents.iter().filter(|parent| growing(parent)).count();
+        let mut siblings = vec![Siblings::default(); parents.len()];
+        let next = out.len();
+        for _ in 0..params.children_of(depth).saturating_mul(draws) {
+            let i = pool.sample(rng);
+            let mut child_rng = ChildRng::seed_from_u64(rng.gen());
+            let anchor = surfaces.of(&parents[i]).sampler.sample(&mut child_rng);
+            // the twin follows the same generator, so it and all it grows mirror the original
+            let twin = (params.direction == GrowthDirection::Both).then(|| child_rng.clone());
+            let inward = params.direction == GrowthDirection::Inward;
+            let children = std::iter::once((child_rng, inward)).chain(twin.map(|rng| (rng, true)));
+            for (mut rng, inward) in children {
+                if out_of_budget(params, out.len(), max_instances) {
+                    return;
+                }
+                let origin = Origin {
+                    parent: generation.start + i,
+                    triangle: anchor,
+                };
+                if let Some(child) = grow_child(
+                    &mut rng,
+                    surfaces,
+                    params,
+                    &parents[i],
+                    origin,
+                    inward,
+                    &mut siblings[i],
+                ) {
+                    out.push(child);
+                }
+            }
+        }
+        generation = next..out.len();
+    }
+}
+
+/// Grow the children of `out[parent]` without their descendants, queueing each child on `pending`
+/// to be grown later. Returns true once `out` holds `max_instances`.
+fn push_children<R: Rng + ?Sized>(
+    rng: &mut R,
+    surfaces: &Surfaces,
+    params: &GrowthParams,
+    parent: usize,
+    max_instances: usize,
+    out: &mut Vec<Instance>,
+    pending: &mut VecDeque<(usize, ChildRng)>,
+) -> bool {
+    let parent_index = parent;
+    let parent = out[parent];
+    if parent.generation >= parent.max_generation {
+        return false;
+    }
+    let surface = surfaces.of(&parent);
+    let mut siblings = Siblings::default();
+    for (mut rng, anchor, inward) in children(rng, surface.sampler, params, parent.generation) {
+        if out_of_budget(params, out.len(), max_instances) {
+            return true;
+        }
+        let origin = Origin {
+            parent: parent_index,
+            triangle: anchor,
+        };
+        let Some(child) = grow_child(
+            &mut rng,
+            surfaces,
+            params,
+            &parent,
+            origin,
+            inward,
+            &mut siblings,
+        ) else {
+            continue;
+        };
+        out.push(child);
+        pending.push_back((out.len() - 1, rng));
+    }
+    false
+}
+
+/// The children of a copy in `generation`, as each child's own generator, the index of the
+/// triangle it grows on and whether it grows inward.
+///
+/// `rng` is only used for choosing which triangles grow children under `growth_probability` and
+/// for seeding the children, so what each child grows into doesn't depend on its siblings.
+fn children<'a, R: Rng + ?Sized>(
+    rng: &'a mut R,
+    sampler: &'a WeightedSampler,
+    params: &'a GrowthParams,
+    generation: usize,
+) -> impl Iterator<Item = (ChildRng, usize, bool)> + 'a {
+    let mut cursor = ChildCursor::default();
+    std::iter::from_fn(move || next_child(rng, sampler, params, generation, &mut cursor))
+}
+
+/// Where [`next_child`] carries on from, starting from the default.
+#[derive(Clone, Debug, Default)]
+struct ChildCursor {
+    /// Children drawn so far, or under `growth_probability` triangles gone through.
+    next: usize,
+    /// The inward twin of the last child, still to come when growing both ways.
+    twin: Option<(ChildRng, usize)>,
+}
+
+/// The next of the children yielded by [`children`], for walking them one at a time, and whether
+/// it grows inward.
+fn next_child<R: Rng + ?Sized>(
+    rng: &mut R,
+    sampler: &WeightedSampler,
+    params: &GrowthParams,
+    generation: usize,
+    cursor: &mut ChildCursor,
+) -> Option<(ChildRng, usize, bool)> {
+    if let Some((rng, anchor)) = cursor.twin.take() {
+        return Some((rng, anchor, true));
+    }
+    let (rng, anchor) = next_anchor(rng, sampler, params, generation, &mut cursor.next)?;
+    let inward = match params.direction {
+        GrowthDirection::Outward => false,
+        GrowthDirection::Inward => true,
+        GrowthDirection::Both => {
+            // the twin follows the same generator, so it and all it grows mirror the original
+            cursor.twin = Some((rng.clone(), anchor));
+            false
+        }
+    };
+    Some((rng, anchor, inward))
+}
+
+/// A child's own generator and the triangle it grows on, before working out which way it points.
+/// `next` is where to carry on from, starting at 0.
+fn next_anchor<R: Rng + ?Sized>(
+    rng: &mut R,
+    sampler: &WeightedSampler,
+    params: &GrowthParams,
+    generation: usize,
+    next: &mut usize,
+) -> Option<(ChildRng, usize)> {
+    let Some(p) = params.growth_probability else {
+        if *next >= params.children_of(generation) {
+            return None;
+        }
+        *next += 1;
+        let mut rng = ChildRng::seed_from_u64(rng.gen());
+        let anchor = sampler.sample(&mut rng);
+        return Some((rng, anchor));
+    };
+    while *next < sampler.len() {
+        let i = *next;
+        *next += 1;
+        if sampler.weight(i) > 0.0 && rng.gen_bool(p) {
+            return Some((ChildRng::seed_from_u64(rng.gen()), i));
+        }
+    }
+    None
+}
+
+/// Times a child that overlaps its siblings is moved before giving up on it, see
+/// [`GrowthParams::avoid_overlap`].
+pub const OVERLAP_RETRIES: usize = 8;
+
+/// Times a child placed too close to its siblings is thrown again before giving up on it, see
+/// [`Placement::Poisson`].
+pub const POISSON_RETRIES: usize = 30;
+
+/// The children of one copy placed so far, for keeping the next clear of them.
+#[derive(Clone, Debug, Default)]
+struct Siblings {
+    /// Bounding spheres, for [`GrowthParams::avoid_overlap`].
+    bounds: Vec<Sphere>,
+    /// Where each was placed on the surface, for [`Placement::Poisson`].
+    anchors: Vec<Vector3<f32>>,
+}
+
+/// Grow a child of `parent` where `origin` says, inward if `inward`, unless the triangle is too
+/// small or the child overlaps its `siblings` or is too close to them, in which case it may be
+/// moved to another triangle. Children that grow are added to `siblings`, the children of
+/// `parent` so far.
+fn grow_child(
+    rng: &mut ChildRng,
+    surfaces: &Surfaces,
+    params: &GrowthParams,
+    parent: &Instance,
+    mut origin: Origin,
+    inward: bool,
+    siblings: &mut Siblings,
+) -> Option<Instance> {
+    let surface = surfaces.of(parent);
+    let child_mesh = match &surfaces.anchors.chooser {
+        Some(chooser) => chooser.sample(rng),
+        // the child is a generation deeper than its parent, and generation 1 has the first mesh
+        None => parent.generation % surfaces.children.len(),
+    };
+    let child_size = surfaces.child_bounds(child_mesh);
+    let child_bounds = child_size.filter(|_| params.avoid_overlap);
+    let spacing = (params.placement == Placement::Poisson).then_some(params.min_distance);
+    let retries = match (spacing, child_bounds, params.growth_probability) {
+        (Some(_), _, _) => POISSON_RETRIES,
+        (None, Some(_), None) => OVERLAP_RETRIES,
+        _ => 0,
+    };
+    for attempt in 0..=retries {
+        // under growth_probability a child only grows on its own triangle, but can try another
+        // point on it
+        if attempt > 0 && params.growth_probability.is_none() {
+            origin.triangle = surface.sampler.sample(rng);
+        }
+        let triangle = surface.mesh[origin.triangle];
+        if placed_area(params, parent, &triangle) < params.min_triangle_area {
+            continue;
+        }
+        let (child, anchor) = child_of(
+            rng,
+            triangle,
+            surface.center,
+            params,
+            parent,
+            origin,
+            inward,
+        );
+        let child = Instance {
+            child_mesh,
+            ..child
+        };
+        if child_size.is_some_and(|size| {
+            placed_sphere(&size, &child.transform).radius < params.min_feature_size
+        }) {
+            continue;
+        }
+        let anchor = match params.grow_on {
+            GrowOn::Child => place_point(&parent.transform, anchor),
+            GrowOn::Base => anchor,
+        };
+        // a child's twin growing the other way is placed on exactly the same point, and doesn't
+        // count
+        let crowded = spacing.is_some_and(|spacing| {
+            siblings.anchors.iter().any(|other| {
+                let distance = (other - anchor).norm();
+                distance > 0.0 && distance < spacing
+            })
+        });
+        if crowded {
+            continue;
+        }
+        let bounds = child_bounds.map(|bounds| placed_sphere(&bounds, &child.transform));
+        if bounds.is_some_and(|bounds| {
+            siblings
+                .bounds
+                .iter()
+                .any(|other| other.intersects(&bounds))
+        }) {
+            continue;
+        }
+        siblings.bounds.extend(bounds);
+        if spacing.is_some() {
+            siblings.anchors.push(anchor);
+        }
+        return Some(child);
+    }
+    None
+}
+
+/// How much an instance's transform scales the area of what it moves, taking the cube root of how
+/// much it scales volume as if it scaled every axis the same.
+fn area_scale(transform: &Matrix4<Real>) -> f32 {
+    let linear = transform
+        .cast::<f32>()
+        .fixed_view::<3, 3>(0, 0)
+        .into_owned();
+    linear.determinant().abs().powf(2.0 / 3.0)
+}
+
+/// `sphere` moved by an instance's transform, grown by the most the transform stretches in any
+/// direction so it still contains what it did.
+fn placed_sphere(sphere: &Sphere, transform: &Matrix4<Real>) -> Sphere {
+    let stretch = transform
+        .fixed_view::<3, 3>(0, 0)
+        .into_owned()
+        .cast::<f32>()
+        .singular_values()
+        .max();
+    Sphere {
+        center: place_point(transform, sphere.center),
+        radius: sphere.radius * stretch,
+    }
+}
+
+/// Move `v` by an instance's transform.
+fn place_point(transform: &Matrix4<Real>, v: Vector3<f32>) -> Vector3<f32> {
+    transform
+        .transform_point(&v.cast::<Real>().into())
+        .coords
+        .cast()
+}
+
+/// Area of `triangle` of the surface `parent`'s children grow on, as placed in the fractal.
+fn placed_area(params: &GrowthParams, parent: &Instance, triangle: &Triangle) -> f32 {
+    match params.grow_on {
+        GrowOn::Child => area(&triangle.map(|v| place_point(&parent.transform, v))),
+        GrowOn::Base => area(triangle),
+    }
+}
+
+/// Place a single child of `parent` on `triangle`, drawing from the child's own generator, turned
+/// over to point into the surface if `inward`. `triangle` is in the parent's frame, unless growing
+/// on the base. Also returns the point on `triangle` it's placed at.
+fn child_of(
+    rng: &mut ChildRng,
+    triangle: Triangle,
+    center: Vector3<f32>,
+    params: &GrowthParams,
+    parent: &Instance,
+    origin: Origin,
+    inward: bool,
+) -> (Instance, Vector3<f32>) {
+    let point = match params.placement {
+        Placement::Centroid => {
+            let [v0, v1, v2] = triangle;
+            (v0 + v1 + v2) / 3.0
+        }
+        Placement::Random | Placement::Poisson => random_point(rng, &triangle),
+    };
+    let point = match try_normal(&triangle) {
+        Some(normal) if params.jitter > 0.0 => {
+            point + jitter(rng, &triangle, &normal, params.jitter)
+        }
+        _ => point,
+    };
+    let placement = match params.growth_mode {
+        GrowthMode::Surface => place_at(triangle, point),
+        GrowthMode::Radial => place_radially(triangle, point, center),
+    };
+    let placement = match params.orient_up {
+        Some(up) => orient_up(placement, &up),
+        None => placement,
+    };
+    let placement = match params
+        .tangents
+        .as_ref()
+        .and_then(|t| t.get(origin.triangle))
+    {
+        Some(tangent) => orient_up(placement, tangent),
+        None => placement,
+    };
+    // a half turn rather than a reflection, so the child isn't turned inside out
+    let placement = if inward {
+        placement * Matrix4::from_axis_angle(&Vector3::x_axis(), std::f32::consts::PI)
+    } else {
+        placement
+    };
+    // placing points the child's z axis outward, so spinning about z before placing spins about
+    // the direction it grows in
+    let spin = if params.rotate_children {
+        let theta = rng.gen_range(0.0..std::f32::consts::TAU);
+        Matrix4::from_axis_angle(&Vector3::z_axis(), theta)
+    } else {
+        Matrix4::identity()
+    };
+    let tilt = if params.spread_angle > 0.0 {
+        spread(rng, params.spread_angle.to_radians())
+    } else {
+        Matrix4::identity()
+    };
+    let lift = Matrix4::new_translation(&Vector3::new(0.0, 0.0, params.lift));
+    let generation = parent.generation + 1;
+    let scale = match (params.scale_range, params.scale_to_area) {
+        (Some((min, max)), _) => Vector3::repeat(rng.gen_range(min..=max)),
+        (None, Some(k)) => Vector3::repeat(k * area(&triangle).sqrt()),
+        (None, None) => params.scale_for(generation),
+    };
+    let scale = if params.scale_noise > 0.0 {
+        scale * rng.gen_range(1.0 - params.scale_noise..=1.0 + params.scale_noise)
+    } else {
+        scale
+    };
+    let transformation = placement * tilt * lift * spin * Matrix4::new_nonuniform_scaling(&scale);
+    let transform = match params.grow_on {
+        GrowOn::Child => parent.transform * transformation.cast::<Real>(),
+        // placed on the base itself, but as small as if it had grown on its parent
+        GrowOn::Base => (placement
+            * tilt
+            * Matrix4::new_nonuniform_scaling(&parent.size)
+            * lift
+            * spin
+            * Matrix4::new_nonuniform_scaling(&scale))
+        .cast::<Real>(),
+    };
+    let max_generation = match params.branch_depth {
+        Some(branch_depth) => {
+            generation + branch_depth.sample(rng, parent.max_generation - generation)
+        }
+        None => parent.max_generation,
+    };
+    let child = Instance {
+        transform,
+        generation,
+        size: parent.size.component_mul(&scale),
+        origin: Some(origin),
+        max_generation,
+        child_mesh: 0,
+    };
+    (child, point)
+}
+
+/// A sampler choosing which triangles of `mesh` children are placed on, according to `params`.
+/// `role` says which mesh errors are about.
+fn anchor_sampler(
+    mesh: &[Triangle],
+    params: &GrowthParams,
+    role: MeshRole,
+) -> Result<WeightedSampler, FractulateError> {
+    let selection = params.selection_weight.weights(mesh);
+    let weight = |i: usize, triangle: &Triangle| {
+        let weight = selection[i];
+        match (&params.bias, try_normal(triangle)) {
+            (Some(bias), Some(normal)) => weight * bias.factor(&normal),
+            _ => weight,
+        }
+    };
+    let facing = |triangle: &Triangle| match &params.normal_filter {
+        Some(filter) => try_normal(triangle)
+            .is_some_and(|normal| normal.dot(&filter.direction) > filter.min_cos),
+        None => true,
+    };
+    let in_region = |triangle: &Triangle| {
+        params
+            .region
+            .as_ref()
+            .is_none_or(|region| region.contains(triangle))
+    };
+    let marked = |i: usize| {
+        params
+            .grow_mask
+            .as_ref()
+            .is_none_or(|mask| mask.get(i) == Some(&true))
+    };
+    let weights = mesh.iter().enumerate().map(|(i, triangle)| {
+        if facing(triangle) && in_region(triangle) && marked(i) {
+            weight(i, triangle)
+        } else {
+            0.0
+        }
+    });
+    let sampler = WeightedSampler::new(weights);
+    let sampler = sampler.map(|sampler| sampler.with_tolerance(params.sample_tolerance));
+    sampler.ok_or_else(|| {
+        let reason = match (&params.normal_filter, &params.region, &params.grow_mask) {
+            (None, None, None) => return FractulateError::ZeroArea(role),
+            (Some(_), None, None) => "pass the normal filter",
+            (None, Some(_), None) => "are in the region",
+            (Some(_), Some(_), None) => "both pass the normal filter and are in the region",
+            (None, None, Some(_)) => "are marked to grow on",
+            _ => "pass every filter",
+        };
+        FractulateError::NoEligibleTriangles { mesh: role, reason }
+    })
+}
+
+/// Check `mesh` has triangles to grow from, and that every coordinate is finite, since one NaN
+/// spreads through the whole fractal.
+fn check_mesh(mesh: &Mesh, role: MeshRole) -> Result<(), FractulateError> {
+    if mesh.is_empty() {
+        return Err(FractulateError::EmptyInput(role));
+    }
+    match mesh
+        .iter()
+        .position(|triangle| !triangle.iter().flatten().all(|c| c.is_finite()))
+    {
+        Some(triangle) => Err(FractulateError::NonFiniteVertex {
+            mesh: role,
+            triangle,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Choose a random triangle, weighted by its area.
+///
+/// When choosing many times from the same triangles, build an [`area_sampler`] once instead.
+/// `None` if the triangles have no area.
+pub fn select<R: Rng + ?Sized>(rng: &mut R, triangles: &[Triangle]) -> Option<Triangle> {
+    Some(triangles[area_sampler(triangles)?.sample(rng)])
+}
+
+/// A sampler that picks triangles with probability proportional to their area. Degenerate
+/// triangles are never picked. `None` if the triangles have no area.
+pub fn area_sampler(triangles: &[Triangle]) -> Option<WeightedSampler> {
+    WeightedSampler::new(SelectionWeight::Area.weights(triangles))
+}
+
+/// Area of a triangle.
+pub fn area(triangle: &Triangle) -> f32 {
+    let [t0, t1, t2] = triangle;
+    (t1 - t0).cross(&(t2 - t0)).norm() / 2.0
+}
+
+/// Apply `transformation` to every vertex of `mesh`.
+pub fn transform(mut mesh: Mesh, transformation: Matrix4<f32>) -> Mesh {
+    mesh.transform(&transformation);
+    mesh
+}
+
+/// Unit normal of a triangle, following the right hand rule. Zero for degenerate triangles, which
+/// is how STL marks a facet with no normal.
+pub fn get_normal(face: &Triangle) -> nalgebra::Vector3<f32> {
+    try_normal(face).unwrap_or_else(Vector3::zeros)
+}
+
+/// Unit normal of a triangle, following the right hand rule, or `None` if the triangle is
+/// degenerate.
+pub fn try_normal(face: &Triangle) -> Option<Vector3<f32>> {
+    if is_degenerate(face) {
+        return None;
+    }
+    let a = face[1] - face[0];
+    let b = face[2] - face[0];
+    Some(a.cross(&b).normalize())
+}
+
+/// Sine of the smallest angle between two edges for a triangle to count as non-degenerate.
+const DEGENERATE_SINE: f32 = 1e-6;
+
+/// Whether a triangle is too close to a line or a point to have a meaningful normal. Triangles with
+/// non-finite vertices are degenerate too.
+pub fn is_degenerate(face: &Triangle) -> bool {
+    let a = face[1] - face[0];
+    let b = face[2] - face[0];
+    // relative to the edge lengths so that it doesn't depend on the size of the triangle
+    let double_area = a.cross(&b).norm();
+    let threshold = DEGENERATE_SINE * a.norm() * b.norm();
+    double_area.is_nan() || double_area <= threshold || !threshold.is_finite()
+}
+
+/// Create a transformation that would move a mesh so it sticks out from the center of the
+/// triangle.
+pub fn place_on_triangle(triangle: Triangle) -> Matrix4<f32> {
+    let [v0, v1, v2] = triangle;
+    place_at(triangle, (v0 + v1 + v2) / 3.0)
+}
+
+/// Create a transformation that moves a mesh so its own `triangle` is centered on the origin
+/// facing down the z axis, undoing [`place_on_triangle`] and turning it over. Placing the moved
+/// mesh then sets that triangle flat on the one it's placed on, with the rest of the mesh sticking
+/// out.
+pub fn attachment(triangle: Triangle) -> Matrix4<f32> {
+    let placement = place_on_triangle(triangle)
+        .try_inverse()
+        .expect("placements only rotate and translate");
+    Matrix4::from_axis_angle(&Vector3::x_axis(), std::f32::consts::PI) * placement
+}
+
+/// Create a transformation that would move a mesh so it sticks out from `point`, oriented to the
+/// triangle.
+///
+/// The mesh's z axis is pointed along the triangle's normal and its x axis along the first edge of
+/// the triangle that has a length. Degenerate triangles get an arbitrary orientation.
+pub fn place_at(triangle: Triangle, point: Vector3<f32>) -> Matrix4<f32> {
+    let [v0, v1, v2] = triangle;
+
+    let (normal, x_axis) = match try_normal(&triangle) {
+        Some(normal) => {
+            let x_axis = [v1 - v0, v2 - v0]
+                .iter()
+                .find_map(|edge| edge.try_normalize(f32::MIN_POSITIVE))
+                .unwrap_or_else(|| perpendicular(&normal));
+            (normal, x_axis)
+        }
+        None => (Vector3::z(), Vector3::x()),
+    };
+    let y_axis = normal.cross(&x_axis);
+    let rotation = Matrix4::new(
+        x_axis.x, y_axis.x, normal.x, 0.0, x_axis.y, y_axis.y, normal.y, 0.0, x_axis.z, y_axis.z,
+        normal.z, 0.0, 0.0, 0.0, 0.0, 1.0,
+    );
+
+    let translation = Matrix4::new_translation(&point);
+
+    translation * rotation
+}
+
+/// Like [`place_at`], but pointing the mesh's z axis straight away from `center` rather than along
+/// the triangle's normal. The x axis is still as close to the triangle's first edge as it can be.
+pub fn place_radially(
+    triangle: Triangle,
+    point: Vector3<f32>,
+    center: Vector3<f32>,
+) -> Matrix4<f32> {
+    let Some(z_axis) = (point - center).try_normalize(f32::MIN_POSITIVE) else {
+        return place_at(triangle, point);
+    };
+    let [v0, v1, v2] = triangle;
+    let x_axis = [v1 - v0, v2 - v0]
+        .iter()
+        .find_map(|edge| (edge - z_axis * edge.dot(&z_axis)).try_normalize(edge.norm() * 1e-6))
+        .unwrap_or_else(|| perpendicular(&z_axis));
+    let y_axis = z_axis.cross(&x_axis);
+    let rotation = Matrix4::new(
+        x_axis.x, y_axis.x, z_axis.x, 0.0, x_axis.y, y_axis.y, z_axis.y, 0.0, x_axis.z, y_axis.z,
+        z_axis.z, 0.0, 0.0, 0.0, 0.0, 1.0,
+    );
+    Matrix4::new_translation(&point) * rotation
+}
+
+/// Turn `placement`, as made by [`place_at`] or [`place_radially`], about the axis it points the
+/// mesh's z axis along, so the mesh's x axis points as close to `up` as it can. Left alone if `up`
+/// is parallel to that axis.
+pub fn orient_up(placement: Matrix4<f32>, up: &Vector3<f32>) -> Matrix4<f32> {
+    let z_axis: Vector3<f32> = placement.fixed_view::<3, 1>(0, 2).into();
+    let Some(x_axis) = (up - z_axis * up.dot(&z_axis)).try_normalize(up.norm() * 1e-6) else {
+        return placement;
+    };
+    let y_axis = z_axis.cross(&x_axis);
+    let mut ret = placement;
+    ret.fixed_view_mut::<3, 1>(0, 0).copy_from(&x_axis);
+    ret.fixed_view_mut::<3, 1>(0, 1).copy_from(&y_axis);
+    ret
+}
+
+/// The unit direction across `triangle` in which the first of the texture coordinates `uvs` at
+/// its corners grows, or `None` if they don't span it.
+pub fn uv_tangent(triangle: &Triangle, uvs: &[Vector2<f32>; 3]) -> Option<Vector3<f32>> {
+    let [p0, p1, p2] = triangle;
+    let [t0, t1, t2] = uvs;
+    let (e1, e2) = (p1 - p0, p2 - p0);
+    let (d1, d2) = (t1 - t0, t2 - t0);
+    let det = d1.perp(&d2);
+    if det.abs() < f32::MIN_POSITIVE {
+        return None;
+    }
+    // solving for the change in position per unit of u, holding v still
+    let tangent = (e1 * d2.y - e2 * d1.y) * det.signum();
+    tangent.try_normalize(f32::MIN_POSITIVE)
+}
+
+/// A rotation turning the z axis to a random direction within `half_angle` radians of it, uniform
+/// over that part of the sphere.
+fn spread(rng: &mut ChildRng, half_angle: f32) -> Matrix4<f32> {
+    // uniform in height up the sphere is uniform over its surface
+    let cos = rng.gen_range(half_angle.cos()..=1.0);
+    let azimuth = rng.gen_range(0.0..std::f32::consts::TAU);
+    let axis = Vector3::new(-azimuth.sin(), azimuth.cos(), 0.0);
+    Matrix4::from_axis_angle(
+        &nalgebra::Unit::new_unchecked(axis),
+        cos.clamp(-1.0, 1.0).acos(),
+    )
+}
+
+/// A random offset across the plane of `triangle`, which faces `normal`, uniformly distributed over
+/// a disc of radius `radius` times the square root of its area.
+fn jitter(
+    rng: &mut ChildRng,
+    triangle: &Triangle,
+    normal: &Vector3<f32>,
+    radius: f32,
+) -> Vector3<f32> {
+    let x_axis = perpendicular(normal);
+    let y_axis = normal.cross(&x_axis);
+    // sqrt keeps the density uniform rather than bunched up in the middle
+    let distance = radius * area(triangle).sqrt() * rng.gen::<f32>().sqrt();
+    let theta = rng.gen_range(0.0..std::f32::consts::TAU);
+    (x_axis * theta.cos() + y_axis * theta.sin()) * distance
+}
+
+/// A point chosen uniformly at random from the surface of a triangle.
+pub fn random_point<R: Rng + ?Sized>(rng: &mut R, triangle: &Triangle) -> Vector3<f32> {
+    let [v0, v1, v2] = triangle;
+    // sqrt keeps the density uniform rather than bunched up around v0
+    let s = rng.gen::<f32>().sqrt();
+    let t = rng.gen::<f32>();
+    v0 * (1.0 - s) + v1 * (s * (1.0 - t)) + v2 * (s * t)
+}
+
+/// Some unit vector perpendicular to the unit vector `v`.
+pub(crate) fn perpendicular(v: &Vector3<f32>) -> Vector3<f32> {
+    // crossing with the axis least aligned with v is the most numerically stable choice
+    let axis = if v.x.abs() < v.y.abs() && v.x.abs() < v.z.abs() {
+        Vector3::x()
+    } else if v.y.abs() < v.z.abs() {
+        Vector3::y()
+    } else {
+        Vector3::z()
+    };
+    v.cross(&axis).normalize()
+}
+
+/// Unit tetrahedron with outward facing triangles.
+#[cfg(test)]
+pub(crate) fn tetrahedron() -> Mesh {
+    let o = Vector3::zeros();
+    let [x, y, z] = [Vector3::x(), Vector3::y(), Vector3::z()];
+    Mesh::new(vec![[o, y, x], [o, x, z], [o, z, y], [x, y, z]])
+}
+
+/// Unit cube, two outward facing triangles per face.
+#[cfg(test)]
+pub(crate) fn cube() -> Vec<Triangle> {
+    let corner = |i: usize| Vector3::new((i & 1) as f32, (i >> 1 & 1) as f32, (i >> 2) as f32);
+    let quads = [
+        [0, 2, 3, 1],
+        [4, 5, 7, 6],
+        [0, 1, 5, 4],
+        [2, 6, 7, 3],
+        [0, 4, 6, 2],
+        [1, 3, 7, 5],
+    ];
+    quads
+        .iter()
+        .flat_map(|[a, b, c, d]| [[*a, *b, *c], [*a, *c, *d]])
+        .map(|face| face.map(corner))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(x: f32, y: f32, z: f32) -> Vector3<f32> {
+        Vector3::new(x, y, z)
+    }
+
+    #[test]
+    fn degenerate_triangles() {
+        let point = [v(1.0, 1.0, 1.0); 3];
+        let segment = [v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0), v(2.0, 0.0, 0.0)];
+        let good = [v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0), v(0.0, 1.0, 0.0)];
+        for triangle in [point, segment] {
+            assert!(is_degenerate(&triangle));
+            assert_eq!(try_normal(&triangle), None);
+            assert_eq!(get_normal(&triangle), Vector3::zeros());
+        }
+        assert!(!is_degenerate(&good));
+        assert_eq!(get_normal(&good), v(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn select_skips_degenerate() {
+        let mesh = [
+            [v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0), v(2.0, 0.0, 0.0)],
+            [v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0), v(0.0, 1.0, 0.0)],
+            [v(3.0, 3.0, 3.0); 3],
+        ];
+        let sampler = area_sampler(&mesh).unwrap();
+        let mut rng = ChildRng::seed_from_u64(0);
+        for _ in 0..1000 {
+            assert_eq!(sampler.sample(&mut rng), 1);
+        }
+    }
+
+    #[test]
+    fn inverse_area_takes_tiny_triangles() {
+        let tiny = [v(0.0, 0.0, 0.0), v(1e-9, 0.0, 0.0), v(0.0, 1e-9, 0.0)];
+        let unit = [v(0.0, 0.0, 1.0), v(1.0, 0.0, 1.0), v(0.0, 1.0, 1.0)];
+        assert!(!is_degenerate(&tiny));
+        let mesh = [unit, tiny, unit, tiny];
+        let weights = SelectionWeight::InverseArea.weights(&mesh);
+        // relative to the smallest, so nothing is weighted above one however small it gets
+        assert_eq!(weights[1], 1.0);
+        assert_eq!(weights[3], 1.0);
+        assert!((weights[0] - 2.0 * area(&tiny)).abs() < 1e-20);
+        let params = GrowthParams {
+            selection_weight: SelectionWeight::InverseArea,
+            ..Default::default()
+        };
+        let sampler = anchor_sampler(&mesh, &params, MeshRole::Base).unwrap();
+        let mut rng = ChildRng::seed_from_u64(0);
+        for _ in 0..1000 {
+            assert_eq!(sampler.sample(&mut rng) % 2, 1);
+        }
+    }
+
+    #[test]
+    fn select_weights_by_area() {
+        let small = [v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0), v(0.0, 2.0, 0.0)];
+        let large = [v(0.0, 0.0, 1.0), v(3.0, 0.0, 1.0), v(0.0, 2.0, 1.0)];
+        let mut rng = ChildRng::seed_from_u64(0);
+        let draws = 40_000;
+        let small_draws = (0..draws)
+            .filter(|_| select(&mut rng, &[small, large]) == Some(small))
+            .count();
+        // a quarter of the area, give or take about four and a half standard deviations
+        let share = small_draws as f64 / draws as f64;
+        assert!((share - 0.25).abs() < 0.01, "{share}");
+    }
+
+    #[test]
+    fn same_output_whatever_the_threads() {
+        let tetrahedron = tetrahedron();
+        let params = GrowthParams {
+            depth: 3,
+            placement: Placement::Random,
+            rotate_children: true,
+            ..Default::default()
+        };
+        // everything that runs in parallel, from growing through post processing
+        let run = || {
+            let instances = instances(
+                &tetrahedron,
+                &tetrahedron,
+                &params,
+                &mut ChildRng::seed_from_u64(1),
+            )
+            .unwrap();
+            let grown = instantiate(&tetrahedron, &tetrahedron, &instances);
+            let indexed = index(&tetrahedron);
+            let grown_indexed = instantiate_indexed(&indexed, &indexed, &instances);
+            let mut welded = weld(&grown, 0.0);
+            smooth(&mut welded, 2, 0.5);
+            displace(&mut welded, 0.1, 1.0, 1);
+            let pruned = prune_hidden(&welded.triangles().collect(), 8);
+            (grown.to_vec(), grown_indexed, pruned.to_vec())
+        };
+        let outputs: Vec<_> = [1, 2, 7]
+            .into_iter()
+            .map(|threads| {
+                rayon::ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .build()
+                    .unwrap()
+                    .install(run)
+            })
+            .collect();
+        assert!(outputs.iter().all(|output| *output == outputs[0]));
+    }
+
+    fn assert_orthonormal(m: &Matrix4<f32>) {
+        assert!(m.iter().all(|c| c.is_finite()));
+        let rotation = m.fixed_view::<3, 3>(0, 0);
+        let identity = rotation.transpose() * rotation;
+        assert!((identity - nalgebra::Matrix3::identity()).norm() < 1e-5);
+        // a rotation, not a reflection
+        assert!((rotation.determinant() - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn place_on_sliver() {
+        let slivers = [
+            // first edge too short to normalize
+            [v(0.0, 0.0, 0.0), v(1e-30, 0.0, 0.0), v(0.0, 1.0, 0.0)],
+            [v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0), v(0.5, 1e-3, 0.0)],
+            [v(0.0, 0.0, 0.0), v(0.0, 0.0, 0.0), v(0.0, 0.0, 0.0)],
+            [v(0.0, 0.0, 0.0), v(1.0, 1.0, 1.0), v(2.0, 2.0, 2.0)],
+        ];
+        for sliver in slivers {
+            assert_orthonormal(&place_on_triangle(sliver));
+        }
+        for normal in [v(1.0, 0.0, 0.0), v(0.0, -1.0, 0.0), v(0.6, 0.0, 0.8)] {
+            let p = perpendicular(&normal);
+            assert!(p.dot(&normal).abs() < 1e-6);
+            assert!((p.norm() - 1.0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn place_on_random_triangles() {
+        let mut rng = ChildRng::seed_from_u64(0);
+        let mut placed = 0;
+        while placed < 100 {
+            let triangle: Triangle =
+                [(); 3].map(|_| Vector3::from_fn(|_, _| rng.gen_range(-10.0..10.0)));
+            if is_degenerate(&triangle) {
+                continue;
+            }
+            let m = place_on_triangle(triangle);
+            assert_orthonormal(&m);
+            let up = m.transform_vector(&Vector3::z());
+            assert!((up - get_normal(&triangle)).norm() < 1e-5);
+            let centroid = (triangle[0] + triangle[1] + triangle[2]) / 3.0;
+            assert!(
+                (m.transform_point(&nalgebra::Point3::origin()).coords - centroid).norm() < 1e-4
+            );
+            placed += 1;
+        }
+    }
+
+    #[test]
+    fn attach_by_a_face() {
+        let mut tetrahedron = tetrahedron();
+        let m = attachment(tetrahedron[3]);
+        assert_orthonormal(&m);
+        tetrahedron.transform(&m);
+        // the face lies on the xy plane facing down, and the rest stands up off it
+        let face = tetrahedron[3];
+        assert!(face.iter().all(|v| v.z.abs() < 1e-6));
+        assert!((get_normal(&face) + Vector3::z()).norm() < 1e-6);
+        assert!((face[0] + face[1] + face[2]).norm() < 1e-6);
+        assert!(tetrahedron[0][0].z > 0.5);
+    }
+
+    #[test]
+    fn orient_up_lines_up_x() {
+        let up = v(0.0, 0.0, 1.0);
+        let wall = [v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.5), v(0.0, 0.0, 1.0)];
+        let m = orient_up(place_on_triangle(wall), &up);
+        assert_orthonormal(&m);
+        assert!((m.transform_vector(&Vector3::x()) - up).norm() < 1e-6);
+        assert_eq!(m.transform_point(&nalgebra::Point3::origin()).z, 0.5);
+        // facing up there's no way to turn towards up
+        let floor = [v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0), v(0.0, 1.0, 0.0)];
+        assert_eq!(
+            orient_up(place_on_triangle(floor), &up),
+            place_on_triangle(floor)
+        );
+    }
+
+    #[test]
+    fn uv_tangent_follows_u() {
+        let t = Vector2::new;
+        let floor = [v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0), v(0.0, 1.0, 0.0)];
+        // u runs along y and v against x
+        let uvs = [t(0.0, 0.0), t(0.0, -2.0), t(2.0, 0.0)];
+        let tangent = uv_tangent(&floor, &uvs).unwrap();
+        assert!((tangent - v(0.0, 1.0, 0.0)).norm() < 1e-6);
+        // mirrored texture coordinates still give the way u grows
+        let mirrored = [t(0.0, 0.0), t(0.0, 2.0), t(2.0, 0.0)];
+        let tangent = uv_tangent(&floor, &mirrored).unwrap();
+        assert!((tangent - v(0.0, 1.0, 0.0)).norm() < 1e-6);
+        assert_eq!(
+            uv_tangent(&floor, &[t(0.0, 0.0), t(1.0, 1.0), t(2.0, 2.0)]),
+            None
+        );
+    }
+
+    #[test]
+    fn zero_area_is_an_error() {
+        let mesh = Mesh::new(vec![[v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0), v(2.0, 0.0, 0.0)]]);
+        let mut rng = ChildRng::seed_from_u64(0);
+        assert!(area_sampler(&mesh).is_none());
+        assert!(matches!(
+            instances(&mesh, &mesh, &GrowthParams::default(), &mut rng),
+            Err(FractulateError::ZeroArea(MeshRole::Base))
+        ));
+        let no_growth = GrowthParams {
+            depth: 0,
+            ..Default::default()
+        };
+        assert_eq!(
+            instances(&mesh, &mesh, &no_growth, &mut rng).unwrap().len(),
+            1
+        );
+    }
+
+    #[test]
+    fn bad_input_is_told_apart() {
+        let good = Mesh::new(vec![[v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0), v(0.0, 1.0, 0.0)]]);
+        let nan = Mesh::new(vec![good[0], [v(0.0, 0.0, f32::NAN); 3]]);
+        let params = GrowthParams::default();
+        let mut rng = ChildRng::seed_from_u64(0);
+        assert!(matches!(
+            instances(&Mesh::default(), &good, &params, &mut rng),
+            Err(FractulateError::EmptyInput(MeshRole::Base))
+        ));
+        assert!(matches!(
+            instances(&good, &nan, &params, &mut rng),
+            Err(FractulateError::NonFiniteVertex {
+                mesh: MeshRole::Child,
+                triangle: 1
+            })
+        ));
+        assert!(matches!(
+            grow_bytes(b"not a mesh", &params, 0),
+            Err(FractulateError::InvalidStl(_))
+        ));
+    }
+
+    #[test]
+    fn traversals_grow_the_same_fractal() {
+        let tetrahedron = tetrahedron();
+        let params = GrowthParams {
+            depth: 3,
+            num_children: 3,
+            placement: Placement::Random,
+            rotate_children: true,
+            ..Default::default()
+        };
+        let grow = |traversal| {
+            let params = GrowthParams {
+                traversal,
+                ..params.clone()
+            };
+            instances(
+                &tetrahedron,
+                &tetrahedron,
+                &params,
+                &mut ChildRng::seed_from_u64(7),
+            )
+            .unwrap()
+        };
+        let dfs = grow(Traversal::DepthFirst);
+        let bfs = grow(Traversal::BreadthFirst);
+        assert_eq!(dfs.len(), 1 + 3 + 9 + 27);
+        assert_eq!(dfs.len(), bfs.len());
+        // breadth first lists each generation in the order depth first visits it
+        let by_generation = |instances: &[Instance]| {
+            let mut instances = instances.to_vec();
+            instances.sort_by_key(|instance| instance.generation);
+            instances
+                .iter()
+                .map(|instance| instance.transform)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(by_generation(&dfs), by_generation(&bfs));
+        assert!(bfs.windows(2).all(|w| w[0].generation <= w[1].generation));
+        for instances in [&dfs, &bfs] {
+            assert_eq!(instances[0].origin, None);
+            for (i, instance) in instances.iter().enumerate().skip(1) {
+                let origin = instance.origin.unwrap();
+                assert!(origin.parent < i);
+                assert_eq!(instances[origin.parent].generation + 1, instance.generation);
+                assert!(origin.triangle < tetrahedron.len());
+            }
+        }
+    }
+
+    #[test]
+    fn grow_on_previous_spreads_children_over_the_generation() {
+        let tetrahedron = tetrahedron();
+        let params = GrowthParams {
+            depth: 3,
+            num_children: 3,
+            grow_on_previous: true,
+            ..Default::default()
+        };
+        let mut uneven = false;
+        for seed in 0..8 {
+            let mut rng = ChildRng::seed_from_u64(seed);
+            let instances = instances(&tetrahedron, &tetrahedron, &params, &mut rng).unwrap();
+            // as many children as growing on every copy, a generation at a time
+            assert_eq!(instances.len(), 1 + 3 + 9 + 27);
+            assert!(instances
+                .windows(2)
+                .all(|w| w[0].generation <= w[1].generation));
+            let mut children = vec![0; instances.len()];
+            for (i, instance) in instances.iter().enumerate().skip(1) {
+                let origin = instance.origin.unwrap();
+                assert!(origin.parent < i);
+                assert_eq!(instances[origin.parent].generation + 1, instance.generation);
+                children[origin.parent] += 1;
+            }
+            // the root has nothing to share with, but later copies needn't get three each
+            assert_eq!(children[0], 3);
+            uneven |= children[1..13].iter().any(|&count| count != 3);
+        }
+        assert!(uneven);
+    }
+
+    #[test]
+    fn scale_ratio_decays() {
+        let params = GrowthParams {
+            child_scale: Vector3::repeat(0.5),
+            scale_ratio: 0.5,
+            ..Default::default()
+        };
+        assert_eq!(params.scale_for(1), Vector3::repeat(0.5));
+        assert_eq!(params.scale_for(3), Vector3::repeat(0.125));
+        assert_eq!(params.size_of(3), Vector3::repeat(0.5 * 0.25 * 0.125));
+    }
+
+    #[test]
+    fn scale_noise_varies_children() {
+        let base = Mesh::new(vec![[v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0), v(0.0, 1.0, 0.0)]]);
+        let params = GrowthParams {
+            depth: 1,
+            num_children: 20,
+            scale_noise: 0.2,
+            ..Default::default()
+        };
+        let grow = || instances(&base, &base, &params, &mut ChildRng::seed_from_u64(0)).unwrap();
+        let sizes: Vec<f32> = grow()[1..].iter().map(|instance| instance.size.x).collect();
+        assert!(sizes.iter().all(|size| (0.4..=0.6).contains(size)));
+        assert!(sizes.iter().any(|&size| size != sizes[0]));
+        let again: Vec<f32> = grow()[1..].iter().map(|instance| instance.size.x).collect();
+        assert_eq!(sizes, again);
+    }
+
+    #[test]
+    fn scale_to_area_follows_triangles() {
+        let base = Mesh::new(vec![
+            [v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0), v(0.0, 1.0, 0.0)],
+            [v(0.0, 0.0, 1.0), v(4.0, 0.0, 1.0), v(0.0, 2.0, 1.0)],
+        ]);
+        let params = GrowthParams {
+            depth: 1,
+            scale_to_area: Some(2.0),
+            ..Default::default()
+        };
+        let instances = instances(&base, &base, &params, &mut ChildRng::seed_from_u64(0)).unwrap();
+        for instance in &instances[1..] {
+            let triangle = &base[instance.origin.unwrap().triangle];
+            let expected = 2.0 * area(triangle).sqrt();
+            assert!((instance.size.x - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn spread_stays_in_the_cone() {
+        let floor = Mesh::new(vec![[v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0), v(0.0, 1.0, 0.0)]]);
+        let params = GrowthParams {
+            depth: 1,
+            num_children: 200,
+            spread_angle: 30.0,
+            ..Default::default()
+        };
+        let instances =
+            instances(&floor, &floor, &params, &mut ChildRng::seed_from_u64(0)).unwrap();
+        let tilts: Vec<f32> = instances[1..]
+            .iter()
+            .map(|instance| {
+                let transform = instance.transform.cast::<f32>();
+                let up = transform.transform_vector(&Vector3::z()).normalize();
+                up.z.clamp(-1.0, 1.0).acos().to_degrees()
+            })
+            .collect();
+        assert!(tilts.iter().all(|&tilt| tilt <= 30.0 + 1e-3));
+        assert!(tilts.iter().any(|&tilt| tilt > 25.0));
+    }
+
+    #[test]
+    fn min_feature_size_ends_branches() {
+        let tetrahedron = tetrahedron();
+        let radius = bounding_sphere(&tetrahedron).unwrap().radius;
+        // children are half their parent's size, so the third generation is the last big enough
+        let params = GrowthParams {
+            depth: 20,
+            num_children: 2,
+            min_feature_size: radius / 8.0 * 0.99,
+            ..Default::default()
+        };
+        let mut rng = ChildRng::seed_from_u64(0);
+        let instances = instances(&tetrahedron, &tetrahedron, &params, &mut rng).unwrap();
+        assert_eq!(instances.len(), 1 + 2 + 4 + 8);
+        assert!(instances.iter().all(|instance| instance.generation <= 3));
+    }
+
+    #[test]
+    fn region_limits_anchors() {
+        let base = Mesh::new(vec![
+            [v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0), v(0.0, 1.0, 0.0)],
+            [v(0.0, 0.0, 1.0), v(1.0, 0.0, 1.0), v(0.0, 1.0, 1.0)],
+        ]);
+        let mut params = GrowthParams {
+            depth: 1,
+            region: Some(Region {
+                min: v(-1.0, -1.0, 0.5),
+                max: v(1.0, 1.0, 2.0),
+            }),
+            ..Default::default()
+        };
+        let mut rng = ChildRng::seed_from_u64(0);
+        let grown = instances(&base, &base, &params, &mut rng).unwrap();
+        assert!(grown[1..]
+            .iter()
+            .all(|instance| instance.origin.unwrap().triangle == 1));
+        params.region.as_mut().unwrap().min.z = 3.0;
+        params.region.as_mut().unwrap().max.z = 4.0;
+        assert!(instances(&base, &base, &params, &mut rng).is_err());
+        // a mask picks triangles out by index instead
+        params.region = None;
+        params.grow_mask = Some(vec![true]);
+        let grown = instances(&base, &base, &params, &mut rng).unwrap();
+        assert!(grown[1..]
+            .iter()
+            .all(|instance| instance.origin.unwrap().triangle == 0));
+        params.grow_mask = Some(vec![false, false]);
+        assert!(instances(&base, &base, &params, &mut rng).is_err());
+    }
+
+    #[test]
+    fn area_epsilon_stops_once_settled() {
+        let tetrahedron = tetrahedron();
+        // each generation adds half the area of the last, a third, a seventh then a fifteenth
+        // of the total so far
+        let params = GrowthParams {
+            depth: 10,
+            num_children: 2,
+            area_epsilon: Some(0.1),
+            ..Default::default()
+        };
+        let mut rng = ChildRng::seed_from_u64(0);
+        let grown = instances(&tetrahedron, &tetrahedron, &params, &mut rng).unwrap();
+        assert_eq!(grown.len(), 1 + 2 + 4 + 8);
+        let lazy = grow_iter(
+            &tetrahedron,
+            &tetrahedron,
+            &params,
+            ChildRng::seed_from_u64(0),
+        );
+        assert_eq!(lazy.unwrap().count(), grown.len() * tetrahedron.len());
+    }
+
+    #[test]
+    fn deep_branches_stay_within_budget() {
+        let tetrahedron = tetrahedron();
+        // one child each makes a single branch, far deeper than recursion would have room for
+        let params = GrowthParams {
+            depth: 1_000_000,
+            num_children: 1,
+            max_triangles: Some(4 * 20_000),
+            ..Default::default()
+        };
+        let mut rng = ChildRng::seed_from_u64(0);
+        let instances = instances(&tetrahedron, &tetrahedron, &params, &mut rng).unwrap();
+        assert_eq!(instances.len(), 20_000);
+        assert_eq!(instances[instances.len() - 1].generation, 20_000 - 1);
+    }
+
+    #[test]
+    fn poisson_children_keep_apart() {
+        let base = Mesh::new(vec![[v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0), v(0.0, 1.0, 0.0)]]);
+        let params = GrowthParams {
+            depth: 1,
+            num_children: 50,
+            placement: Placement::Poisson,
+            min_distance: 0.2,
+            ..Default::default()
+        };
+        let mut rng = ChildRng::seed_from_u64(0);
+        let grown = instances(&base, &base, &params, &mut rng).unwrap();
+        // far fewer fit than were asked for
+        assert!(grown.len() > 2 && grown.len() < 30, "{}", grown.len());
+        let at = |instance: &Instance| place_point(&instance.transform, Vector3::zeros());
+        for (i, a) in grown[1..].iter().enumerate() {
+            for b in &grown[i + 2..] {
+                assert!((at(a) - at(b)).norm() >= 0.2);
+            }
+        }
+    }
+
+    #[test]
+    fn children_per_generation() {
+        let base = Mesh::new(vec![[v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0), v(0.0, 1.0, 0.0)]]);
+        let params = GrowthParams {
+            depth: 4,
+            children_schedule: vec![4, 3, 1],
+            ..Default::default()
+        };
+        let mut rng = ChildRng::seed_from_u64(0);
+        let instances = instances(&base, &base, &params, &mut rng).unwrap();
+        let copies = |generation| {
+            instances
+                .iter()
+                .filter(|instance| instance.generation == generation)
+                .count()
+        };
+        assert_eq!([1, 2, 3, 4].map(copies), [4, 12, 12, 12]);
+        assert_eq!(
+            triangle_count(1, 1, &params),
+            instances.len(),
+            "counting agrees with growing"
+        );
+    }
+
+    #[test]
+    fn params_files_fill_in_defaults() {
+        let params: GrowthParams =
+            serde_json::from_str(r#"{"depth": 4, "traversal": "bfs", "placement": "random"}"#)
+                .unwrap();
+        assert_eq!(params.depth, 4);
+        assert_eq!(params.traversal, Traversal::BreadthFirst);
+        assert_eq!(params.placement, Placement::Random);
+        assert_eq!(params.num_children, GrowthParams::default().num_children);
+        let round_trip: GrowthParams =
+            serde_json::from_str(&serde_json::to_string(&params).unwrap()).unwrap();
+        assert_eq!(format!("{round_trip:?}"), format!("{params:?}"));
+    }
+
+    #[test]
+    fn indexed_instances_match() {
+        let tetrahedron = tetrahedron();
+        let child = Mesh::new(tetrahedron[..2].to_vec());
+        let params = GrowthParams {
+            depth: 3,
+            rotate_children: true,
+            ..Default::default()
+        };
+        let instances = instances(
+            &tetrahedron,
+            &child,
+            &params,
+            &mut ChildRng::seed_from_u64(0),
+        )
+        .unwrap();
+        let indexed = instantiate_indexed(&index(&tetrahedron), &index(&child), &instances);
+        assert_eq!(indexed.vertices.len(), 4 + (instances.len() - 1) * 4);
+        assert_eq!(
+            indexed.triangles().collect::<Vec<_>>(),
+            instantiate(&tetrahedron, &child, &instances).to_vec()
+        );
+    }
+
+    #[test]
+    fn grow_both_ways() {
+        let base = Mesh::new(vec![[v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0), v(0.0, 1.0, 0.0)]]);
+        let params = GrowthParams {
+            depth: 2,
+            num_children: 2,
+            direction: GrowthDirection::Both,
+            ..Default::default()
+        };
+        let instances = instances(&base, &base, &params, &mut ChildRng::seed_from_u64(0)).unwrap();
+        assert_eq!(instances.len(), unbounded_copies(&params));
+        assert_eq!(instances.len(), 1 + 4 + 16);
+        let up = |instance: &Instance| instance.transform.transform_vector(&Vector3::z()).z;
+        let children: Vec<&Instance> = instances
+            .iter()
+            .filter(|instance| instance.generation == 1)
+            .collect();
+        assert_eq!(children.len(), 4);
+        // outward then inward twin, each turned rather than reflected
+        for pair in children.chunks(2) {
+            assert!(up(pair[0]) > 0.0);
+            assert!(up(pair[1]) < 0.0);
+            assert!(pair.iter().all(|child| !reflects(&child.transform)));
+        }
+    }
+
+    #[test]
+    fn grow_several_children() {
+        let triangle = Mesh::new(vec![[v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0), v(0.0, 1.0, 0.0)]]);
+        let children = [triangle.clone(), triangle.subdivided()];
+        let params = GrowthParams {
+            depth: 3,
+            num_children: 4,
+            ..Default::default()
+        };
+        let grow = |params: &GrowthParams| {
+            let mut rng = ChildRng::seed_from_u64(0);
+            instances_with(&triangle, &children, params, &mut rng).unwrap()
+        };
+        let instances = grow(&params);
+        let used = |instances: &[Instance], i: usize| {
+            instances
+                .iter()
+                .filter(|instance| instance.generation > 0 && instance.child_mesh == i)
+                .count()
+        };
+        assert!(used(&instances, 0) > 0 && used(&instances, 1) > 0);
+        // grandchildren grow on the mesh their parent is a copy of
+        for instance in &instances[1..] {
+            let parent = &instances[instance.origin.unwrap().parent];
+            let triangles = if parent.generation == 0 {
+                1
+            } else {
+                children[parent.child_mesh].len()
+            };
+            assert!(instance.origin.unwrap().triangle < triangles);
+        }
+        let mesh = instantiate_with(&triangle, &children, &instances);
+        assert_eq!(mesh.len(), instance_triangles(1, &[1, 4], &instances));
+        let only_second = grow(&GrowthParams {
+            child_weights: vec![0.0, 1.0],
+            ..params.clone()
+        });
+        assert_eq!(used(&only_second, 1), only_second.len() - 1);
+        let alternating = grow(&GrowthParams {
+            child_choice: ChildChoice::ByGeneration,
+            ..params.clone()
+        });
+        for instance in &alternating[1..] {
+            assert_eq!(instance.child_mesh, (instance.generation - 1) % 2);
+        }
+        for child_weights in [vec![1.0], vec![-1.0, 2.0], vec![f32::NAN, 1.0]] {
+            let bad_weights = GrowthParams {
+                child_weights,
+                ..params.clone()
+            };
+            assert!(matches!(
+                instances_with(
+                    &triangle,
+                    &children,
+                    &bad_weights,
+                    &mut ChildRng::seed_from_u64(0)
+                ),
+                Err(FractulateError::ChildWeights { children: 2 })
+            ));
+        }
+    }
+
+    #[test]
+    fn mirrored_children_face_out() {
+        let tetrahedron = tetrahedron();
+        let params = GrowthParams {
+            depth: 1,
+            num_children: 2,
+            ..Default::default()
+        };
+        let mut instances = instances(
+            &tetrahedron,
+            &tetrahedron,
+            &params,
+            &mut ChildRng::seed_from_u64(0),
+        )
+        .unwrap();
+        mirror_children(&mut instances, Plane::Yz);
+        assert_eq!(instances.len(), 1 + 2 * 2);
+        let volume = |instance: &Instance| {
+            let copy = instantiate(&tetrahedron, &tetrahedron, std::slice::from_ref(instance));
+            Stats::of(&copy).volume
+        };
+        for (child, mirrored) in instances[1..3].iter().zip(&instances[3..]) {
+            assert!((volume(child) - volume(mirrored)).abs() < 1e-6);
+            assert!(volume(mirrored) > 0.0);
+        }
+    }
+
+    #[test]
+    fn twist_turns_with_height() {
+        let mut mesh = Mesh::new(vec![[v(1.0, 0.0, 0.0), v(1.0, 0.0, 1.0), v(0.0, 2.0, 2.0)]]);
+        mesh.twist(Axis::Z, std::f32::consts::FRAC_PI_2);
+        let expected = [v(1.0, 0.0, 0.0), v(0.0, 1.0, 1.0), v(0.0, -2.0, 2.0)];
+        for (twisted, expected) in mesh[0].iter().zip(&expected) {
+            assert!((twisted - expected).norm() < 1e-6, "{twisted:?}");
+        }
+    }
+
+    #[test]
+    fn anchors_lie_on_the_base() {
+        let tetrahedron = tetrahedron();
+        let params = GrowthParams {
+            depth: 1,
+            num_children: 8,
+            lift: 0.3,
+            placement: Placement::Random,
+            rotate_children: true,
+            ..Default::default()
+        };
+        let mut rng = ChildRng::seed_from_u64(0);
+        let instances = instances(&tetrahedron, &tetrahedron, &params, &mut rng).unwrap();
+        let anchors: Vec<_> = anchor_points(&instances, params.lift).collect();
+        assert_eq!(anchors.len(), 8);
+        for (anchor, child) in anchors.iter().zip(&instances[1..]) {
+            let triangle = tetrahedron[child.origin.unwrap().triangle];
+            let normal = get_normal(&triangle);
+            assert!((anchor - triangle[0]).dot(&normal).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn exploded_branches_move_together() {
+        let tetrahedron = tetrahedron();
+        let params = GrowthParams {
+            depth: 3,
+            num_children: 2,
+            ..Default::default()
+        };
+        let planned = instances(
+            &tetrahedron,
+            &tetrahedron,
+            &params,
+            &mut ChildRng::seed_from_u64(0),
+        )
+        .unwrap();
+        let mut exploded = planned.clone();
+        explode(
+            &mut exploded,
+            &tetrahedron,
+            std::slice::from_ref(&tetrahedron),
+            2.0,
+        );
+        let moved = |i: usize| -> Vector3<Real> {
+            (exploded[i].transform - planned[i].transform)
+                .fixed_view::<3, 1>(0, 3)
+                .into()
+        };
+        assert_eq!(moved(0), Vector3::zeros());
+        for (i, instance) in planned.iter().enumerate().skip(1) {
+            // shape and orientation stay the same
+            let linear =
+                |instance: &Instance| instance.transform.fixed_view::<3, 3>(0, 0).into_owned();
+            assert_eq!(linear(instance), linear(&exploded[i]));
+            let mut branch = i;
+            while planned[branch].generation > 1 {
+                branch = planned[branch].origin.unwrap().parent;
+            }
+            assert!((moved(i) - moved(branch)).amax() < 1e-5);
+        }
+        let center = Stats::of(&tetrahedron).centroid().unwrap();
+        for (i, instance) in planned.iter().enumerate() {
+            if instance.generation == 1 {
+                let at = place_point(&instance.transform, center);
+                assert!((moved(i).cast::<f32>() - (at - center) * 2.0).amax() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn grow_on_base() {
+        let base = Mesh::new(vec![[v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0), v(0.0, 1.0, 0.0)]]);
+        let child = Mesh::new(vec![[v(0.0, 0.0, 1.0), v(1.0, 0.0, 1.0), v(0.0, 1.0, 1.0)]]);
+        let params = GrowthParams {
+            depth: 3,
+            num_children: 2,
+            placement: Placement::Random,
+            grow_on: GrowOn::Base,
+            ..Default::default()
+        };
+        let mut rng = ChildRng::seed_from_u64(0);
+        let instances = instances(&base, &child, &params, &mut rng).unwrap();
+        assert_eq!(instances.len(), 1 + 2 + 4 + 8);
+        for instance in &instances[1..] {
+            // every generation sits right on the base, which is flat
+            let origin = instance
+                .transform
+                .transform_point(&nalgebra::Point3::origin());
+            assert!(origin.z.abs() < 1e-6);
+            let size = instance
+                .transform
+                .transform_vector(&Vector3::x())
+                .cast::<f32>()
+                .norm();
+            assert!((size - params.size_of(instance.generation).x).abs() < 1e-6);
+            assert_eq!(instance.size, params.size_of(instance.generation));
+        }
+        let mesh = instantiate(&base, &child, &instances);
+        assert_eq!(mesh.len(), instances.len());
+        assert_eq!(mesh[0], base[0]);
+    }
+
+    #[test]
+    fn radial_children_point_away_from_center() {
+        let tetrahedron = tetrahedron();
+        let center = Stats::of(&tetrahedron).centroid().unwrap();
+        let params = GrowthParams {
+            depth: 1,
+            num_children: 20,
+            placement: Placement::Random,
+            rotate_children: true,
+            growth_mode: GrowthMode::Radial,
+            ..Default::default()
+        };
+        let mut rng = ChildRng::seed_from_u64(0);
+        let instances = instances(&tetrahedron, &tetrahedron, &params, &mut rng).unwrap();
+        for instance in &instances[1..] {
+            let transform = instance.transform.cast::<f32>();
+            assert_orthonormal(&(transform * Matrix4::new_scaling(2.0)));
+            let origin = transform
+                .transform_point(&nalgebra::Point3::origin())
+                .coords;
+            let outward = (origin - center).normalize();
+            let z = transform.transform_vector(&Vector3::z()).normalize();
+            assert!((z - outward).norm() < 1e-5);
+        }
+    }
+}
@@ -1,142 +1,3008 @@
-use anyhow::Result;
-use nalgebra::{Matrix4, Vector3};
-use rand::{Rng, SeedableRng};
-use std::io::{Cursor, Read};
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use clap::parser::ValueSource;
+use clap::{ArgGroup, ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand};
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use fractulate::format::obj::{self, Group};
+use fractulate::format::{csv, ply, stl, Format, Rgb, WriteOptions};
+use fractulate::{
+    AreaHistogram, Axis, Bias, BoxCounter, BranchDepth, ChildChoice, CompactMesh, FractulateError,
+    GrowOn, GrowthDirection, GrowthMode, GrowthParams, Instance, Mesh, NormalFilter, Placement,
+    Plane, Preview, Real, Region, Search, SelectionWeight, Sphere, Stats, Traversal, Triangle,
+    DEFAULT_SAMPLE_TOLERANCE,
+};
+use log::{debug, info, warn, Level, LevelFilter};
+use nalgebra::{Matrix4, Vector2, Vector3};
+use rand::{Rng, RngCore, SeedableRng};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Cursor, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-use stl_io::{read_stl, write_stl, Normal, Triangle, Vertex};
+/// Deeper than this and the output is measured in billions of triangles.
+const MAX_DEPTH: i64 = 16;
+
+/// Each round of subdivision quadruples the input's triangles.
+const MAX_SUBDIVISIONS: i64 = 10;
+
+/// How many copies to generate at once when streaming output.
+const STREAM_CHUNK: usize = 1024;
+
+/// What --center puts on the origin. The two only agree for symmetric shapes. A fractal that grew
+/// more on one side keeps its bounding box center near the middle of its extent, while its
+/// centroid moves towards where most of its surface is.
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum Center {
+    /// The center of the bounding box, halfway between the furthest points along each axis. Only
+    /// the extremes count, so a stray spike moves it as much as a whole branch.
+    Bbox,
+    /// The centroid of the surface, the average of the triangles' centroids weighted by their
+    /// area. Where the surface is, so a dense cluster of small children pulls it their way. Not
+    /// the center of mass of the solid the surface encloses.
+    Centroid,
+}
+
+/// How --branch-depth cuts branches short.
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum DepthDistribution {
+    /// Each child's branch stops anywhere between it and where its parent's does, all equally
+    /// likely.
+    Uniform,
+    /// Each child grows a Poisson distributed number of generations beneath it, with mean
+    /// --branch-depth-mean, but never more than its parent's branch.
+    Poisson,
+}
+
+/// Random number generators --rng can grow from. Each seeds the generator of every child, which
+/// always uses xoshiro256**, so a different algorithm places different children rather than
+/// changing how a placed child draws its own options.
+#[derive(Clone, Copy, Debug, Default, clap::ValueEnum)]
+enum RngAlgorithm {
+    /// xoshiro256**, fast and small.
+    #[default]
+    Xoshiro,
+    /// xoshiro256++, a close relative with a different output function.
+    XoshiroPlusPlus,
+    /// ChaCha with 8 rounds, slower but cryptographically strong.
+    Chacha,
+}
+
+impl RngAlgorithm {
+    /// A generator of this algorithm seeded from `seed`. The only place the binary picks one.
+    fn seeded(self, seed: u64) -> Box<dyn RngCore> {
+        match self {
+            RngAlgorithm::Xoshiro => {
+                Box::new(rand_xoshiro::Xoshiro256StarStar::seed_from_u64(seed))
+            }
+            RngAlgorithm::XoshiroPlusPlus => {
+                Box::new(rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(seed))
+            }
+            RngAlgorithm::Chacha => Box::new(rand_chacha::ChaCha8Rng::seed_from_u64(seed)),
+        }
+    }
+}
+
+/// Tools that don't grow anything, run as `fractulate <command>`. Input and output options like
+/// --stl-ascii and --weld-epsilon can be given before or after the command.
+#[derive(Subcommand)]
+enum Command {
+    /// Convert a mesh to another format without growing anything.
+    ///
+    /// The format is guessed from the output's extension unless --output-format is given, and a
+    /// .gz on the end gzips it, as does --gzip. OBJ and PLY output share vertices as
+    /// --weld-epsilon and --weld-angle say, and an STL's header is carried over to STL output
+    /// unless --header-text replaces it. Options for growing are refused.
+    Convert {
+        /// Mesh to read, in any format fractulate reads.
+        input: PathBuf,
+        /// Where to write it.
+        output: PathBuf,
+    },
+}
+
+/// Generates a fractal from a mesh.
+#[derive(Parser)]
+#[command(group(ArgGroup::new("patterned").args(["variants", "split_by_generation"])))]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Meshes to grow the fractal from, combined into one surface. Read from stdin when omitted.
+    input: Vec<PathBuf>,
+
+    /// Where to write the fractal. Written to stdout when omitted.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Grow this many fractals from consecutive seeds, starting at --seed, rather than one. Each
+    /// is written to --out-pattern with {} replaced by its seed, so the seed of one worth keeping
+    /// is in its name.
+    #[arg(
+        long,
+        requires = "out_pattern",
+        conflicts_with_all = [
+            "output", "csv_out", "tree_out", "anchors_out", "preview", "recompute_normals_only",
+        ],
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    variants: Option<u32>,
+
+    /// Where to write each of the --variants, or each generation with --split-by-generation, such
+    /// as fractal_{}.stl with {} replaced by the seed or generation.
+    #[arg(long, requires = "patterned", value_parser = parse_pattern)]
+    out_pattern: Option<String>,
+
+    /// Write each generation to its own file at --out-pattern, the input as generation 0, to load
+    /// them separately or print them in different materials. Generations where nothing grew are
+    /// left out. Every file is moved by --fit-size and the like together, so they still line up.
+    #[arg(
+        long,
+        requires = "out_pattern",
+        conflicts_with_all = [
+            "output", "only_generation", "check_watertight", "compact", "color_by_depth",
+            "dedup_triangles", "smooth", "displace", "decimate", "merge_coplanar", "prune_hidden",
+            "twist", "orient_output", "wireframe", "bake_ao",
+        ]
+    )]
+    split_by_generation: bool,
+
+    /// Mesh to grow as children, instead of copies of the input. Repeat to give several, and each
+    /// child is a copy of one of them chosen at random, all equally likely unless --child-weights
+    /// says otherwise, or by generation with --child-choice. The choice is seeded like everything
+    /// else, so a seed still grows the same fractal. --max-triangles is spent as if every child
+    /// were the largest.
+    #[arg(long)]
+    child: Vec<PathBuf>,
+
+    /// How likely each --child is to be chosen, in proportion, as a weight for each in the order
+    /// they're given, such as 3,1 for three of the first to every one of the second.
+    #[arg(long, value_delimiter = ',', requires = "child", value_parser = parse_non_negative)]
+    child_weights: Vec<f32>,
+
+    /// How each child picks which --child it's a copy of. `by-generation` cycles through them in
+    /// the order they're given, one generation each, so with two the generations alternate
+    /// between them, and with more start over from the first after the last.
+    #[arg(long, value_enum, default_value_t = ChildChoice::Random, requires = "child")]
+    child_choice: ChildChoice,
+
+    /// Turn each --child over so its lowest triangle, by the height of its center, sits flat on
+    /// the triangle it grows on with the rest of the child sticking out, rather than standing the
+    /// child on its own origin with its z axis out.
+    #[arg(long, requires = "child")]
+    attach_lowest: bool,
+
+    /// Like --attach-lowest, but setting down the first triangle of this group or object of each
+    /// --child, which must be an OBJ naming it with `g` or `o`.
+    #[arg(
+        long,
+        value_name = "GROUP",
+        requires = "child",
+        conflicts_with = "attach_lowest"
+    )]
+    attach_group: Option<String>,
+
+    /// What children's children grow on. `child` grows each generation on the triangles the last
+    /// one added, so the fractal decorates itself, `base` grows every generation on the input,
+    /// each smaller than the last.
+    #[arg(long, value_enum, default_value_t = GrowOn::Child)]
+    grow_on: GrowOn,
+
+    /// Spread each generation's children over the whole generation before it, as if its copies
+    /// were one mesh, rather than growing --children on every copy. Bigger copies get more, and
+    /// some none, so the fractal grows over itself rather than each copy decorating itself.
+    /// Grows a generation at a time, whatever --traversal says.
+    #[arg(long)]
+    grow_on_previous: bool,
+
+    /// Read the growth options from this .json or .toml file, with options given on the command
+    /// line taking precedence. Fields are named as in the library's `GrowthParams`, `depth`,
+    /// `num_children`, `child_scale` and so on, and any left out take the usual defaults.
+    #[arg(long)]
+    params: Option<PathBuf>,
+
+    /// Number of generations to grow. 0 outputs the base mesh unchanged.
+    #[arg(long, default_value_t = 2, value_parser = clap::value_parser!(u32).range(0..=MAX_DEPTH))]
+    depth: u32,
+
+    /// Stop each branch at a random depth rather than growing them all to --depth, for a less
+    /// regular fractal. The same seed always stops the same branches.
+    #[arg(long, value_enum)]
+    branch_depth: Option<DepthDistribution>,
+
+    /// Average generations each child grows beneath it under --branch-depth poisson.
+    #[arg(
+        long,
+        default_value_t = 2.0,
+        requires = "branch_depth",
+        value_parser = parse_non_negative
+    )]
+    branch_depth_mean: f32,
+
+    /// Children grown per generation. 0 grows nothing.
+    #[arg(long, default_value_t = 5)]
+    children: usize,
+
+    /// Comma separated children for each copy to grow by generation, overriding --children. The
+    /// input grows the first number and its children the second. Generations past the end of the
+    /// list use the last number.
+    #[arg(long, value_delimiter = ',', conflicts_with = "children")]
+    children_per_depth: Vec<usize>,
+
+    /// Instead of a fixed number of children, give every triangle this chance of growing one.
+    #[arg(
+        long,
+        conflicts_with_all = ["children", "children_per_depth"],
+        value_parser = parse_probability
+    )]
+    growth_probability: Option<f64>,
+
+    /// Size of each child relative to its parent. 1.0 grows children the same size as the parent.
+    /// Defaults to 0.5.
+    #[arg(long, value_parser = parse_positive)]
+    scale: Option<f32>,
+
+    /// Shrink each generation's children by this much more than the last's, so generation g has
+    /// children --scale * ratio^(g-1) the size of their parent. The default of 1.0 scales every
+    /// generation alike. Growing n children each a fraction s of their parent's size gives a
+    /// fractal of dimension about log(n) / log(1/s), so this tunes how the dimension changes with
+    /// depth.
+    #[arg(long, default_value_t = 1.0, value_parser = parse_positive, conflicts_with = "scales")]
+    scale_ratio: f32,
+
+    /// Comma separated scale for each generation, overriding --scale. Generations past the end of
+    /// the list use the last scale.
+    #[arg(long, value_delimiter = ',', value_parser = parse_positive, conflicts_with = "scale")]
+    scales: Vec<f32>,
+
+    /// Size of each child relative to its parent along its own x,y,z axes, stretching children
+    /// rather than scaling them evenly as --scale does. z is along the normal of the triangle the
+    /// child grows on, x along that triangle's first edge and y across it.
+    #[arg(long, value_parser = parse_size, conflicts_with_all = ["scale", "scales"])]
+    scale_xyz: Option<Vector3<f32>>,
+
+    /// Give each child a random scale between min,max, overriding any other scale options.
+    #[arg(long, value_parser = parse_range)]
+    scale_range: Option<(f32, f32)>,
+
+    /// Scale each child to this times the square root of the area of the triangle it grows on,
+    /// overriding --scale and the options like it, so children on small triangles are small and
+    /// those on big ones big. The same scale along every axis.
+    #[arg(
+        long,
+        value_parser = parse_positive,
+        conflicts_with_all = ["scale", "scales", "scale_xyz", "scale_ratio", "scale_range"]
+    )]
+    scale_to_area: Option<f32>,
+
+    /// Multiply each child's scale by a random factor between 1 - noise and 1 + noise, on top of
+    /// the other scale options. Each generation keeps the size --scale-ratio gives it on average,
+    /// with every child varying around it. Must be less than 1.
+    #[arg(long, default_value_t = 0.0, value_parser = parse_noise)]
+    scale_noise: f32,
+
+    /// Seed for the random number generator. The same seed and input always give the same output.
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Pick a seed from OS entropy and print it to stderr.
+    #[arg(long, conflicts_with = "seed")]
+    random_seed: bool,
+
+    /// Random number generator to grow from. The same seed grows a different fractal with each.
+    #[arg(long, value_enum, default_value_t)]
+    rng: RngAlgorithm,
+
+    /// Format of the input and any --child meshes. Guessed from the contents of each when omitted.
+    #[arg(long, value_enum, global = true)]
+    format: Option<Format>,
+
+    /// Where on its triangle each child is placed.
+    #[arg(long, value_enum, default_value_t = Placement::Centroid)]
+    placement: Placement,
+
+    /// How close children of the same parent may be to each other under --placement poisson, in
+    /// a straight line between where they're placed on the surface, in the output's units before
+    /// any --fit-size. Children with no room left are left out, and how many is reported.
+    #[arg(long, default_value_t = 0.0, value_parser = parse_non_negative)]
+    min_distance: f32,
+
+    /// Cover the input's surface with a single layer of children this far apart, in the units of
+    /// the input mesh, rather than growing a fractal. As many children as could fit are placed by
+    /// --placement poisson, each scaled so it's this wide along its x or y axis, whichever is
+    /// wider, so neighbors about meet. An approximate tiling, not an exact cover: expect gaps and
+    /// some overlap, especially with children that aren't square.
+    #[arg(
+        long,
+        value_parser = parse_positive,
+        conflicts_with_all = [
+            "depth", "branch_depth", "children", "children_per_depth", "growth_probability",
+            "placement", "min_distance", "scale", "scales", "scale_xyz", "scale_range",
+            "scale_to_area",
+        ]
+    )]
+    cover: Option<f32>,
+
+    /// Nudge each child up to this far across its triangle from where --placement puts it, in a
+    /// random direction. Measured in square roots of the triangle's area, so 0.2 keeps children
+    /// near their triangle's center while breaking up the regular pattern.
+    #[arg(long, default_value_t = 0.0, value_parser = parse_non_negative)]
+    jitter: f32,
+
+    /// Which way children point: `surface` along the normal of their triangle, `radial` straight
+    /// away from the center of what they grow on.
+    #[arg(long, value_enum, default_value_t = GrowthMode::Surface)]
+    growth_mode: GrowthMode,
+
+    /// Which side of the surface children grow on. `inward` turns them over to grow into it, and
+    /// `both` grows an inward twin of every outward child, doubling the children.
+    #[arg(long, value_enum, default_value_t = GrowthDirection::Outward)]
+    direction: GrowthDirection,
+
+    /// Turn each child so its x axis points as close to this direction, given as x,y,z, as it can,
+    /// rather than along its triangle's first edge, so children line up with each other.
+    #[arg(long, value_parser = parse_direction, allow_hyphen_values = true)]
+    orient_up: Option<Vector3<f32>>,
+
+    /// Turn each child so its x axis follows the direction the input's U texture coordinate
+    /// grows across its triangle, so decorations follow the flow of a texture laid out on it. An
+    /// OBJ input's `vt` coordinates are used. Children on triangles without any keep the usual
+    /// orientation, or --orient-up's. Needs children that are copies of the input.
+    #[arg(long, conflicts_with = "child")]
+    follow_uvs: bool,
+
+    /// Spin each child by a random angle about its triangle's normal.
+    #[arg(long)]
+    rotate_children: bool,
+
+    /// Tilt each child a random way up to this many degrees from the direction it would point,
+    /// spreading children evenly over the cone around it. 0 keeps them pointing straight out, and
+    /// 90 scatters them over the whole hemisphere.
+    #[arg(long, default_value_t = 0.0, value_parser = parse_angle)]
+    spread_angle: f32,
+
+    /// Raise each child this far off its triangle, in the units of the input mesh.
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    lift: f32,
+
+    /// Don't grow children on triangles smaller than this where they end up, in the units of the
+    /// input mesh. Makes growth peter out once it gets too fine to print.
+    #[arg(long, default_value_t = 0.0, value_parser = parse_non_negative)]
+    min_triangle_area: f32,
+
+    /// Don't grow children smaller than this where they end up, measured as the radius of a
+    /// sphere around each, in the units of the input mesh. Each branch grows until it gets this
+    /// fine, rather than all growing --depth generations, so raise --depth to let it. --depth and
+    /// --max-triangles still stop it.
+    #[arg(long, default_value_t = 0.0, value_parser = parse_non_negative)]
+    min_feature_size: f32,
+
+    /// How near the end of the triangles, as a share of all their weight, a pick can land before
+    /// it's taken to have got there by rounding and given to the last triangle that can grow
+    /// anything. --stats counts how many picks did. A few units in the last place by default,
+    /// which is finer with the f64 feature.
+    #[arg(long, default_value_t = DEFAULT_SAMPLE_TOLERANCE, value_parser = parse_tolerance)]
+    sample_tolerance: Real,
+
+    /// Keep children from overlapping their siblings, moving any that would to another triangle, or
+    /// leaving them out after a few tries. Judged by bounding spheres, so children of spiky meshes
+    /// are kept further apart than they need to be.
+    #[arg(long)]
+    avoid_overlap: bool,
+
+    /// Only grow on triangles facing this direction, given as x,y,z.
+    #[arg(long, value_parser = parse_direction, allow_hyphen_values = true)]
+    normal_filter: Option<Vector3<f32>>,
+
+    /// Minimum cosine between a triangle's normal and --normal-filter for it to be grown on.
+    #[arg(
+        long,
+        default_value_t = 0.0,
+        allow_negative_numbers = true,
+        requires = "normal_filter",
+        value_parser = parse_cosine
+    )]
+    normal_threshold: f32,
+
+    /// Only grow on triangles whose centroid is inside this box, given as
+    /// xmin,ymin,zmin,xmax,ymax,zmax, like the top of a model. The box is in the input's frame,
+    /// and picks out the same part of every copy.
+    #[arg(long, value_parser = parse_box, allow_hyphen_values = true)]
+    region_box: Option<(Vector3<f32>, Vector3<f32>)>,
+
+    /// Only grow on the triangles in this group or object of an OBJ input, named by its `g` or
+    /// `o` statements. Pass it again to grow on several. Children are copies of the input and grow
+    /// on the same group, so this can't be used with --child.
+    #[arg(long, value_name = "GROUP", conflicts_with = "child")]
+    grow_on_group: Vec<String>,
+
+    /// Make triangles facing this direction, given as x,y,z, likelier to grow children.
+    #[arg(long, value_parser = parse_direction, allow_hyphen_values = true)]
+    bias_direction: Option<Vector3<f32>>,
+
+    /// How much --bias-direction matters, from 0 for not at all to 1 for never growing on
+    /// triangles facing the opposite way.
+    #[arg(
+        long,
+        default_value_t = 0.5,
+        value_parser = parse_fraction,
+        requires = "bias_direction"
+    )]
+    bias_strength: f32,
+
+    /// How likely each triangle is to grow a child: in proportion to its area, in inverse
+    /// proportion to favor fine detail, or all alike.
+    #[arg(long, value_enum, default_value_t = SelectionWeight::Area)]
+    selection_weight: SelectionWeight,
+
+    /// Skip triangles with NaN or infinite coordinates, rather than refusing the input.
+    #[arg(long)]
+    drop_invalid: bool,
+
+    /// Skip collapsed triangles, those with two or three vertices the same, which are really
+    /// segments or points. They never grow children either way, but are otherwise kept and
+    /// warned about.
+    #[arg(long)]
+    drop_collapsed: bool,
+
+    /// Refuse input where more than this fraction of the triangles are collapsed, as a mesh that
+    /// broken is probably not what was meant to be grown.
+    #[arg(long, value_parser = parse_fraction)]
+    max_collapsed: Option<f32>,
+
+    /// Rewind the input's triangles so they all face outward, for meshes with inconsistent winding.
+    #[arg(long)]
+    orient: bool,
+
+    /// Split each of the input's triangles into four this many times before growing, giving
+    /// children more places to grow. Multiplies the input's triangles by 4^N.
+    #[arg(
+        long,
+        default_value_t = 0,
+        value_parser = clap::value_parser!(u32).range(0..=MAX_SUBDIVISIONS)
+    )]
+    subdivide: u32,
+
+    /// Repeat the input in a grid this many times along x,y,z before growing, after any
+    /// --subdivide, so children decorate an array of it. Where children grow is chosen from the
+    /// whole grid, so --children is shared between the tiles rather than grown on each. Without a
+    /// --child, children are still copies of the untiled input.
+    #[arg(long, value_parser = parse_counts, conflicts_with = "recompute_normals_only")]
+    tile: Option<[u32; 3]>,
+
+    /// Distance between neighboring --tile copies, along every axis. By default each axis is
+    /// spaced by the input's size along it, so the copies just touch.
+    #[arg(long, requires = "tile", value_parser = parse_positive)]
+    tile_spacing: Option<f32>,
+
+    /// Scale the input so its bounding box's diagonal is 1 before growing, so options in its
+    /// units, like --lift and --min-triangle-area, mean the same whatever units it came in. Any
+    /// --child is scaled by the same factor, keeping it the same size relative to the input. The
+    /// output is scaled back to the input's units afterwards, unless --fit-size sets its size,
+    /// though --stats reports the inputs as normalized.
+    #[arg(long, conflicts_with = "recompute_normals_only")]
+    normalize_input: bool,
+
+    /// Turn the input inside out, so children grow inward and output normals point in.
+    #[arg(long)]
+    flip_normals: bool,
+
+    /// Stop growing once the output would exceed this many triangles.
+    #[arg(long)]
+    max_triangles: Option<usize>,
+
+    /// Stop growing after this many seconds, keeping what has grown so far.
+    #[arg(long, value_parser = parse_seconds)]
+    time_limit: Option<Duration>,
+
+    /// Fail before building the output if it looks like it would take more than this many MiB of
+    /// memory, rather than be killed for running out. Estimated from the copies grown, the output
+    /// where it has to be held whole rather than streamed, and any merging of its vertices, so
+    /// it's rough, and leaves out the input and smaller buffers.
+    #[arg(long, value_name = "MIB", value_parser = clap::value_parser!(u64).range(1..))]
+    max_memory: Option<u64>,
+
+    /// Order to grow children in. Under --max-triangles, dfs grows the first branches in full
+    /// while bfs grows every branch evenly.
+    #[arg(long, value_enum, default_value_t = Traversal::DepthFirst)]
+    traversal: Traversal,
+
+    /// Keep growing until a generation adds less than this fraction of the area grown so far, to
+    /// grow until the fractal fills out rather than guess a --depth, and report how deep that
+    /// was. Growth still stops at --depth, which is raised to its limit unless given. Grows
+    /// breadth first, whatever --traversal says. Only settles if each generation adds less area
+    /// than the last, as with a small --scale or few --children.
+    #[arg(long, value_parser = parse_fraction)]
+    area_epsilon: Option<f32>,
+
+    /// Smooth the output this many times, pulling each vertex toward its neighbors. Vertices are
+    /// merged first as with --weld-epsilon. Needs the whole output in memory.
+    #[arg(long, default_value_t = 0)]
+    smooth: usize,
+
+    /// How far each round of --smooth moves vertices toward their neighbors' average, 1.0 going
+    /// all the way.
+    #[arg(long, default_value_t = 0.5, value_parser = parse_positive)]
+    smooth_lambda: f32,
+
+    /// Make the output lumpy by pushing vertices in or out along their normals by up to about this
+    /// far, following smooth noise seeded by --seed. Vertices are merged first as with
+    /// --weld-epsilon. Needs the whole output in memory.
+    #[arg(long)]
+    displace: Option<f32>,
+
+    /// Bumps per unit of distance for --displace.
+    #[arg(long, default_value_t = 1.0, requires = "displace", value_parser = parse_positive)]
+    displace_freq: f32,
+
+    /// Remove triangles with the same three corners as another, whichever way round, as can happen
+    /// where children or --mirror land exactly on each other. Vertices are merged first as with
+    /// --weld-epsilon, so only exact duplicates are removed by default. Reports how many were
+    /// removed to stderr. Needs the whole output in memory.
+    #[arg(long, conflicts_with = "color_by_depth")]
+    dedup_triangles: bool,
+
+    /// Cut the output down to at most this many triangles, after any --smooth and --displace, by
+    /// merging vertices that are close together. Vertices are merged first as with
+    /// --weld-epsilon. Needs the whole output in memory.
+    #[arg(long, conflicts_with = "color_by_depth")]
+    decimate: Option<usize>,
+
+    /// Merge patches of adjacent triangles lying in one plane and triangulate them again with as
+    /// few triangles as their outlines allow, after any --smooth, --displace and --decimate.
+    /// Triangles are in one plane if their normals differ by at most this, about the angle between
+    /// them in radians. Corners on a patch's outline are all kept, so it only saves triangles on
+    /// large flat areas. Vertices are merged first as with --weld-epsilon. Needs the whole output
+    /// in memory.
+    #[arg(long, value_parser = parse_non_negative, conflicts_with = "color_by_depth")]
+    merge_coplanar: Option<f32>,
+
+    /// Twist the output around --twist-axis by this many degrees per unit along it, after any
+    /// --smooth, --displace and --decimate and before --fit-size and the like, for a spiral. Only
+    /// vertices move, so long edges cut across the twist and a tight twist can make the output
+    /// pass through itself, so it may stop being watertight. Needs the whole output in memory.
+    #[arg(long, allow_negative_numbers = true)]
+    twist: Option<f32>,
+
+    /// Axis --twist turns around, through the origin.
+    #[arg(long, value_enum, default_value_t = Axis::Z, requires = "twist")]
+    twist_axis: Axis,
+
+    /// Rewind the output's triangles so they all face outward, as --orient does the input's, after
+    /// any other post processing. For output whose facet normals disagree, say from inputs wound
+    /// both ways, so renderers show some facets black. Which way is out comes from the volume
+    /// enclosed by each connected piece, so this is only reliable where the output is watertight.
+    /// Needs the whole output in memory.
+    #[arg(long)]
+    orient_output: bool,
+
+    /// Replace the output's triangles with a strut along each of their edges, a closed triangular
+    /// prism with its corners this far from the edge, after any other post processing and before
+    /// --fit-size and the like. For seeing the edges, or printing a lattice. Struts overlap where
+    /// they meet rather than being joined. Needs the whole output in memory.
+    #[arg(long, value_parser = parse_positive, conflicts_with = "color_by_depth")]
+    wireframe: Option<f32>,
+
+    /// Give each edge shared between triangles one strut, rather than one for each triangle.
+    /// Vertices are merged first as with --weld-epsilon.
+    #[arg(long, requires = "wireframe")]
+    wireframe_once: bool,
+
+    /// Remove triangles hidden inside the output, such as where children overlap their parents,
+    /// after any --smooth, --displace and --decimate. A triangle is dropped if every ray cast from
+    /// it in --prune-samples directions hits another. Lossy, since a triangle only visible through
+    /// a gap the rays miss is dropped too. Needs the whole output in memory.
+    #[arg(long, conflicts_with = "color_by_depth")]
+    prune_hidden: bool,
+
+    /// Directions rays are cast in for --prune-hidden, spread over the sphere with the half facing
+    /// behind each triangle skipped. More drop fewer visible triangles but take longer.
+    #[arg(
+        long,
+        default_value_t = 32,
+        requires = "prune_hidden",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    prune_samples: u32,
+
+    /// Scale the output to fit inside a box this size, given as x,y,z.
+    #[arg(long, value_parser = parse_size)]
+    fit_size: Option<Vector3<f32>>,
+
+    /// Scale each axis separately to match --fit-size exactly, rather than uniformly to fit inside
+    /// it.
+    #[arg(long, requires = "fit_size")]
+    fit_stretch: bool,
+
+    /// Move the output so it's centered on the origin, after any --fit-size.
+    #[arg(long)]
+    center: bool,
+
+    /// What --center puts on the origin, the bounding box center by default. Centers even without
+    /// --center.
+    #[arg(long, value_enum)]
+    center_on: Option<Center>,
+
+    /// Scale the output by this much, after any --fit-size and --center.
+    #[arg(long, value_parser = parse_positive)]
+    scale_output: Option<f32>,
+
+    /// Rotate the output by x,y,z degrees, after any --scale-output. Rotates about x first, then
+    /// the rotated y, then the twice rotated z.
+    #[arg(long, value_parser = parse_vector, allow_hyphen_values = true)]
+    rotate_euler: Option<Vector3<f32>>,
+
+    /// Shrink the output, if it's any bigger, so no side of its bounding box is longer than this,
+    /// after any --fit-size, --scale-output and --rotate-euler and before --translate, for a
+    /// fractal that fits a printer's bed. In the output's units, which most slicers take as
+    /// millimeters, so give --fit-size or --scale-output too if the input is in other units.
+    /// Scales about the origin, and never enlarges. Rotated output is measured by turning its
+    /// unrotated bounding box, so may be shrunk more than it needs.
+    #[arg(long, value_parser = parse_positive)]
+    max_dimension: Option<f32>,
+
+    /// Move the output by x,y,z, after everything else.
+    #[arg(long, value_parser = parse_vector, allow_hyphen_values = true)]
+    translate: Option<Vector3<f32>>,
+
+    /// Reflect everything that grew across this plane through the origin and add it to the output,
+    /// for a fractal that's symmetric even though where children grow is random. The input isn't
+    /// reflected, so it's best centered on the plane.
+    #[arg(long, value_enum)]
+    mirror: Option<Plane>,
+
+    /// Move each branch grown on the input outward from the input's center by this many times
+    /// how far it is, without changing its shape, to pull overlapping branches apart and see how
+    /// they were placed. Before --mirror, so the mirrored branches move the same way.
+    #[arg(long, value_parser = parse_non_negative)]
+    explode: Option<f32>,
+
+    /// Output just this generation, rather than every generation up to --depth. 0 outputs the
+    /// input unchanged.
+    #[arg(long)]
+    only_generation: Option<usize>,
+
+    /// Leave the input out of the output, keeping only what grew on it.
+    #[arg(long, overrides_with = "keep_base", conflicts_with = "only_generation")]
+    no_base: bool,
+
+    /// Output the input along with what grew on it, the default. Undoes an earlier --no-base.
+    #[arg(long, overrides_with = "no_base")]
+    keep_base: bool,
+
+    /// Check every edge of the output is shared by exactly two triangles, as 3D printing needs,
+    /// and report any that aren't to stderr. Vertices are merged first as with --weld-epsilon.
+    /// Children that intersect without sharing edges aren't caught. Needs the whole output in
+    /// memory.
+    #[arg(long)]
+    check_watertight: bool,
+
+    /// Print statistics about the input and output to stderr.
+    #[arg(long)]
+    stats: bool,
+
+    /// With --stats, also print a histogram of the input's triangle areas, in this many bins
+    /// each covering areas a fixed factor larger than the last. Shows which sizes of triangle
+    /// children will mostly grow on, when growing in proportion to area, to help decide whether
+    /// to --subdivide or change --selection-weight.
+    #[arg(long, requires = "stats", value_parser = clap::value_parser!(u32).range(1..))]
+    area_histogram: Option<u32>,
+
+    /// Report progress to stderr while growing and writing.
+    #[arg(long)]
+    progress: bool,
+
+    /// Print nothing to stderr but errors: no warnings, and nothing asked for like --stats.
+    #[arg(short, long, conflicts_with = "verbose", global = true)]
+    quiet: bool,
+
+    /// Print more about what's going on to stderr. Repeat for even more.
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Also write every output triangle to this file as CSV, a row of the nine coordinates of its
+    /// vertices each, after a header row naming the columns.
+    #[arg(long)]
+    csv_out: Option<PathBuf>,
+
+    /// Also draw the output to this PNG file, flat shaded and seen from above its front right
+    /// corner, for a quick look without opening a viewer.
+    #[arg(long)]
+    preview: Option<PathBuf>,
+
+    /// Width and height of the --preview picture, in pixels.
+    #[arg(
+        long,
+        requires = "preview",
+        default_value_t = 512,
+        value_parser = clap::value_parser!(u32).range(1..=16384)
+    )]
+    preview_size: u32,
+
+    /// Write how the fractal grew to this file as JSON: a list of every copy in the order they
+    /// were placed, with the copy it grew on, which of that copy's triangles it grew on, its
+    /// generation and its transform. Transforms are before any --fit-size and the like.
+    #[arg(long)]
+    tree_out: Option<PathBuf>,
+
+    /// Write where every child was placed on the copy it grew on to this file, as a PLY point
+    /// cloud, to see how they spread over the surface. Text with --ply-ascii. Points are before
+    /// any --fit-size and the like, like --tree-out, and before --explode and --mirror, so there's
+    /// one for each child that grew.
+    #[arg(long)]
+    anchors_out: Option<PathBuf>,
+
+    /// Print the size the output would be to stderr, without growing anything.
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Check the input and any --child meshes are fit to grow from, report what's wrong with
+    /// them to stderr and exit with an error if anything is, without growing or writing anything.
+    /// Checks for triangles, for NaN or infinite coordinates, for degenerate triangles and for
+    /// there being any area at all, and with --check-watertight that every edge is shared by
+    /// exactly two triangles. For linting meshes before they're used.
+    #[arg(
+        long,
+        conflicts_with_all = ["output", "dry_run", "recompute_normals_only", "instance_file"]
+    )]
+    validate: bool,
+
+    /// Describe the input and any --child meshes to stdout, with their vertices merged as with
+    /// --weld-epsilon and how many of their triangles are degenerate, without growing or writing
+    /// anything.
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "output",
+            "dry_run",
+            "recompute_normals_only",
+            "instance_file",
+            "validate",
+        ]
+    )]
+    info: bool,
+
+    /// Write the input straight back out without growing anything, to fix files with missing or
+    /// wrong facet normals. Normals are always worked out afresh from the vertices, and
+    /// degenerate triangles get zero normals. Combines with --orient and --flip-normals.
+    #[arg(long, conflicts_with_all = ["dry_run", "child"])]
+    recompute_normals_only: bool,
+
+    /// Place a copy of the input at each pose in this file rather than growing a fractal, for
+    /// laying out copies exactly. A .json file holds a list of poses like
+    /// {"translation": [x, y, z], "rotation": [x, y, z], "scale": s}, any of which can be left out.
+    /// A .csv file has a header row, then a row of tx,ty,tz,rx,ry,rz,scale per pose. Rotations are
+    /// in degrees, about the axes as for --rotate-euler. Each copy is scaled, rotated, then
+    /// translated. Output options like --fit-size still apply to the whole.
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "child", "dry_run", "recompute_normals_only", "variants", "tree_out", "anchors_out",
+            "color_by_depth", "explode",
+        ]
+    )]
+    instance_file: Option<PathBuf>,
+
+    /// Format of the output mesh.
+    #[arg(long, value_enum, default_value_t = Format::Stl, global = true)]
+    output_format: Format,
+
+    /// Write STL as text rather than binary.
+    #[arg(long, global = true)]
+    stl_ascii: bool,
+
+    /// Start STL output with the header or solid name of the first input, rather than a blank
+    /// one, so it's carried through to the fractal.
+    #[arg(long, conflicts_with = "header_text")]
+    keep_header: bool,
+
+    /// Start STL output with this instead of a blank header, like the options it was grown with.
+    /// Binary STL only has room for 80 characters.
+    #[arg(long, value_parser = parse_header, global = true)]
+    header_text: Option<String>,
+
+    /// Print a short hash of the growth options, seed and meshes grown from, and start STL output
+    /// with it, so --info on the fractal later tells which settings made it. Other formats have
+    /// nowhere to keep it.
+    #[arg(long, conflicts_with_all = ["keep_header", "header_text", "instance_file"])]
+    provenance: bool,
+
+    /// Gzip the output. Gzipped input is always decompressed.
+    #[arg(long, global = true)]
+    gzip: bool,
+
+    /// Write PLY as text rather than binary.
+    #[arg(long, global = true)]
+    ply_ascii: bool,
+
+    /// Write binary output to stdout even when it's a terminal.
+    #[arg(long, global = true)]
+    force: bool,
+
+    /// Merge output vertices closer than this, for formats that share vertices between faces.
+    #[arg(long, default_value_t = 0.0, value_parser = parse_non_negative, global = true)]
+    weld_epsilon: f32,
+
+    /// Keep merged vertices apart where the faces using them turn by more than this many
+    /// degrees, so OBJ and PLY output keeps creases sharp rather than having them shaded smooth,
+    /// at the cost of more vertices. Doesn't change what --smooth and the like see.
+    #[arg(long, value_parser = parse_non_negative, global = true)]
+    weld_angle: Option<f32>,
+
+    /// Gather OBJ and PLY output in under half the memory, by storing each vertex as 16 bit
+    /// steps across the output's bounds and merging vertices that land on the same step. Lossy,
+    /// moving vertices by up to 1/131070 of the bounds' diagonal. Binary STL never needs the
+    /// whole output in memory, so is unaffected. The output is generated twice, once to find its
+    /// bounds.
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "weld_epsilon", "weld_angle", "dedup_triangles", "smooth", "displace", "decimate",
+            "merge_coplanar", "prune_hidden", "twist", "orient_output", "wireframe",
+            "check_watertight", "color_by_depth", "bake_ao",
+        ]
+    )]
+    compact: bool,
+
+    /// Color each generation along a gradient between two hex colors, given as start,end. The
+    /// input gets the start color and the last generation the end. Needs binary STL or PLY output.
+    #[arg(long, value_parser = parse_gradient)]
+    color_by_depth: Option<(Rgb, Rgb)>,
+
+    /// Shade each output vertex gray by how much of the sky it sees, so crevices come out dark, by
+    /// casting about this many rays from it. Vertices are merged first as with --weld-epsilon.
+    /// Needs PLY output and the whole output in memory, and is slow on big fractals.
+    #[arg(
+        long,
+        value_parser = clap::value_parser!(u32).range(1..),
+        conflicts_with_all = ["color_by_depth", "recompute_normals_only"]
+    )]
+    bake_ao: Option<u32>,
+}
+
+fn parse_header(s: &str) -> Result<String> {
+    ensure!(
+        s.bytes().all(|b| b.is_ascii_graphic() || b == b' '),
+        "must be printable ascii on one line"
+    );
+    Ok(s.to_string())
+}
+
+fn parse_pattern(s: &str) -> Result<String> {
+    ensure!(
+        s.contains("{}"),
+        "must contain {{}} to put each seed or generation in"
+    );
+    Ok(s.to_string())
+}
+
+fn parse_vector(s: &str) -> Result<Vector3<f32>> {
+    let coords = s
+        .split(',')
+        .map(|c| c.trim().parse())
+        .collect::<Result<Vec<f32>, _>>()?;
+    let [x, y, z] = coords[..] else {
+        bail!("expected three comma separated numbers");
+    };
+    Ok(Vector3::new(x, y, z))
+}
+
+fn parse_counts(s: &str) -> Result<[u32; 3]> {
+    let counts = s
+        .split(',')
+        .map(|c| c.trim().parse())
+        .collect::<Result<Vec<u32>, _>>()?;
+    let [x, y, z] = counts[..] else {
+        bail!("expected three comma separated counts");
+    };
+    ensure!(x > 0 && y > 0 && z > 0, "counts must be at least 1");
+    Ok([x, y, z])
+}
+
+fn parse_box(s: &str) -> Result<(Vector3<f32>, Vector3<f32>)> {
+    let coords = s
+        .split(',')
+        .map(|c| c.trim().parse())
+        .collect::<Result<Vec<f32>, _>>()?;
+    let [x0, y0, z0, x1, y1, z1] = coords[..] else {
+        bail!("expected six comma separated numbers");
+    };
+    let (min, max) = (Vector3::new(x0, y0, z0), Vector3::new(x1, y1, z1));
+    ensure!(
+        min.iter().zip(&max).all(|(min, max)| min <= max),
+        "min can't be more than max"
+    );
+    Ok((min, max))
+}
+
+fn parse_direction(s: &str) -> Result<Vector3<f32>> {
+    parse_vector(s)?
+        .try_normalize(0.0)
+        .ok_or_else(|| anyhow!("direction can't be zero"))
+}
+
+fn parse_range(s: &str) -> Result<(f32, f32)> {
+    let Some((min, max)) = s.split_once(',') else {
+        bail!("expected min,max");
+    };
+    let (min, max) = (parse_positive(min.trim())?, parse_positive(max.trim())?);
+    ensure!(min <= max, "min can't be more than max");
+    Ok((min, max))
+}
+
+fn parse_size(s: &str) -> Result<Vector3<f32>> {
+    let ret = parse_vector(s)?;
+    ensure!(
+        ret.iter().all(|c| c.is_finite() && *c > 0.0),
+        "sizes must be positive"
+    );
+    Ok(ret)
+}
+
+fn parse_non_negative(s: &str) -> Result<f32> {
+    let ret: f32 = s.parse()?;
+    ensure!(
+        ret.is_finite() && ret >= 0.0,
+        "must be a non-negative number"
+    );
+    Ok(ret)
+}
+
+fn parse_angle(s: &str) -> Result<f32> {
+    let ret: f32 = s.parse()?;
+    ensure!(
+        (0.0..=180.0).contains(&ret),
+        "must be between 0 and 180 degrees"
+    );
+    Ok(ret)
+}
+
+fn parse_seconds(s: &str) -> Result<Duration> {
+    Ok(Duration::try_from_secs_f64(s.parse()?)?)
+}
+
+fn parse_fraction(s: &str) -> Result<f32> {
+    let ret: f32 = s.parse()?;
+    ensure!((0.0..=1.0).contains(&ret), "must be between 0 and 1");
+    Ok(ret)
+}
+
+fn parse_noise(s: &str) -> Result<f32> {
+    let ret: f32 = s.parse()?;
+    ensure!(
+        (0.0..1.0).contains(&ret),
+        "must be at least 0 and less than 1"
+    );
+    Ok(ret)
+}
+
+fn parse_probability(s: &str) -> Result<f64> {
+    let ret: f64 = s.parse()?;
+    ensure!((0.0..=1.0).contains(&ret), "must be between 0 and 1");
+    Ok(ret)
+}
+
+fn parse_tolerance(s: &str) -> Result<Real> {
+    let ret: Real = s.parse()?;
+    ensure!(
+        (0.0..1.0).contains(&ret),
+        "must be at least 0 and less than 1"
+    );
+    Ok(ret)
+}
+
+fn parse_cosine(s: &str) -> Result<f32> {
+    let ret: f32 = s.parse()?;
+    ensure!((-1.0..=1.0).contains(&ret), "must be between -1 and 1");
+    Ok(ret)
+}
+
+fn parse_gradient(s: &str) -> Result<(Rgb, Rgb)> {
+    let Some((start, end)) = s.split_once(',') else {
+        bail!("expected start,end");
+    };
+    Ok((parse_color(start.trim())?, parse_color(end.trim())?))
+}
+
+fn parse_color(s: &str) -> Result<Rgb> {
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    ensure!(
+        hex.len() == 6 && hex.is_ascii(),
+        "colors must be 6 hex digits, like ff8000"
+    );
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16);
+    Ok([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// Color of `generation` on `gradient` for a fractal `depth` generations deep.
+fn depth_color((start, end): (Rgb, Rgb), generation: usize, depth: usize) -> Rgb {
+    let t = generation as f32 / depth.max(1) as f32;
+    std::array::from_fn(|i| {
+        let (start, end) = (f32::from(start[i]), f32::from(end[i]));
+        (start + (end - start) * t).round() as u8
+    })
+}
+
+fn parse_positive(s: &str) -> Result<f32> {
+    let ret: f32 = s.parse()?;
+    ensure!(ret.is_finite() && ret > 0.0, "must be a positive number");
+    Ok(ret)
+}
 
 fn main() -> Result<()> {
-    let mesh = load()?;
-    let mesh = main_transform(mesh);
-    save(mesh)?;
+    let start = Instant::now();
+    let matches = Args::command().get_matches();
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    init_logging(&args);
+    if let Some(Command::Convert { input, output }) = &args.command {
+        let (input, output) = (input.clone(), output.clone());
+        return convert(&input, &output, &mut args, &matches);
+    }
+    ensure!(
+        args.color_by_depth.is_none()
+            || args.output_format == Format::Ply
+            || (args.output_format == Format::Stl && !args.stl_ascii),
+        "--color-by-depth needs binary STL or PLY output"
+    );
+    ensure!(
+        args.bake_ao.is_none() || args.output_format == Format::Ply,
+        "--bake-ao needs PLY output"
+    );
+    let input = load_inputs(&args.input, args.format)?;
+    let mut mesh = input.mesh;
+    if args.keep_header {
+        args.header_text = input.header.clone();
+    }
+    let mut marks = Marks {
+        chosen: match args.grow_on_group.is_empty() {
+            true => None,
+            false => Some(group_mask(&input.groups, &args.grow_on_group, mesh.len())?),
+        },
+        tangents: match args.follow_uvs {
+            true => Some(uv_tangents(&mesh, &input.uvs)?),
+            false => None,
+        },
+    };
+    debug!("read {} input triangles", mesh.len());
+    if args.validate {
+        let mut problems = validate(&mesh, "input mesh", &args);
+        for path in &args.child {
+            let child = load(Some(path), args.format)?.mesh;
+            problems += validate(&child, &format!("child mesh {}", path.display()), &args);
+        }
+        ensure!(problems == 0, "found {problems} problems");
+        return Ok(());
+    }
+    if args.info {
+        describe(&mesh, input.header.as_deref(), "input", &args);
+        for path in &args.child {
+            let child = load(Some(path), args.format)?;
+            let name = format!("child {}", path.display());
+            describe(&child.mesh, child.header.as_deref(), &name, &args);
+        }
+        return Ok(());
+    }
+    drop_invalid(&mut mesh, &mut marks, "input mesh", args.drop_invalid)?;
+    drop_collapsed(&mut mesh, &mut marks, "input mesh", &args)?;
+    let mut children = Vec::with_capacity(args.child.len());
+    // which triangles of each child it may be attached by
+    let mut attach = Vec::with_capacity(args.child.len());
+    for path in &args.child {
+        let loaded = load(Some(path), args.format)?;
+        let mut child = loaded.mesh;
+        let mut marks = Marks::default();
+        if let Some(name) = &args.attach_group {
+            marks.chosen = Some(
+                group_mask(&loaded.groups, std::slice::from_ref(name), child.len())
+                    .with_context(|| format!("can't attach {}", path.display()))?,
+            );
+        }
+        drop_invalid(&mut child, &mut marks, "child mesh", args.drop_invalid)?;
+        drop_collapsed(&mut child, &mut marks, "child mesh", &args)?;
+        children.push(child);
+        attach.push(marks.chosen);
+    }
+    for mesh in std::iter::once(&mut mesh).chain(&mut children) {
+        if args.orient {
+            fractulate::orient(mesh);
+        }
+        if args.flip_normals {
+            mesh.flip_normals();
+        }
+    }
+    if args.attach_lowest || args.attach_group.is_some() {
+        for ((child, mask), path) in children.iter_mut().zip(&attach).zip(&args.child) {
+            let height = |i: usize| child[i].iter().map(|v| v.z).sum::<f32>();
+            let face = match mask {
+                Some(mask) => mask.iter().position(|&attach| attach),
+                None => (0..child.len()).min_by(|&a, &b| height(a).total_cmp(&height(b))),
+            };
+            let face =
+                face.with_context(|| format!("{} has nothing to attach by", path.display()))?;
+            child.transform(&fractulate::attachment(child[face]));
+        }
+    }
+    // what --normalize-input scaled the inputs by, undone on output
+    let input_scale = match args.normalize_input {
+        true => normalize(&mut mesh, &mut children),
+        false => 1.0,
+    };
+    if args.recompute_normals_only {
+        let degenerate = mesh.iter().filter(|t| fractulate::is_degenerate(t)).count();
+        if degenerate > 0 {
+            warn!("{degenerate} degenerate triangles have no normal, writing zero");
+        }
+        let options = write_options(&args);
+        ensure_not_empty(mesh.len())?;
+        let mut out = create_output(args.output.as_deref(), &args)?;
+        fractulate::format::write(&mut out, &mesh, args.output_format, &options)?;
+        out.finish()?;
+        if let Some(path) = &args.preview {
+            write_preview(path, args.preview_size, || mesh.iter().copied())?;
+        }
+        return Ok(());
+    }
+    for _ in 0..args.subdivide {
+        mesh = mesh.subdivided();
+        // each triangle is split into four in its place
+        marks.repeat_each(4);
+    }
+    if let Some(counts) = args.tile {
+        // children are copies of the input, not the whole grid
+        if children.is_empty() {
+            children.push(mesh.clone());
+        }
+        mesh = tile(&mesh, counts, args.tile_spacing)?;
+        marks.repeat(mesh.len());
+        debug!("tiled the input into {} triangles", mesh.len());
+    }
+    let grew_input = args.subdivide > 0 || args.tile.is_some();
+    if grew_input && args.max_triangles.is_some_and(|budget| mesh.len() > budget) {
+        warn!(
+            "the {} input alone has {} triangles, more than --max-triangles",
+            if args.tile.is_some() {
+                "tiled"
+            } else {
+                "subdivided"
+            },
+            mesh.len()
+        );
+    }
+    let children = match children.is_empty() {
+        true => std::slice::from_ref(&mesh),
+        false => &children,
+    };
+    let seed = if args.random_seed {
+        let seed = rand::rngs::OsRng.gen();
+        info!("seed: {seed}");
+        seed
+    } else {
+        args.seed
+    };
+    if let Some(path) = &args.instance_file {
+        let poses =
+            load_poses(path).with_context(|| format!("failed to read {}", path.display()))?;
+        debug!("placing {} copies", poses.len());
+        let transforms: Vec<Matrix4<f32>> = poses.iter().map(Pose::transform).collect();
+        let triangles = transforms.len().saturating_mul(mesh.len());
+        check_memory(&args, memory_estimate(&args, 0, triangles, false))?;
+        let mut placed = post_process(
+            fractulate::apply_transforms(&mesh, &transforms),
+            &args,
+            seed,
+            input_scale,
+        );
+        if !post_processing(&args) {
+            let stats = if needs_measuring(&args) {
+                Stats::of(&placed)
+            } else {
+                Stats::default()
+            };
+            placed.transform(&output_transform(&stats, &args, input_scale));
+        }
+        ensure_not_empty(placed.len())?;
+        if args.check_watertight {
+            report_watertightness(&placed, args.weld_epsilon);
+        }
+        if let Some(path) = &args.csv_out {
+            write_csv(path, placed.iter().copied())?;
+        }
+        if let Some(path) = &args.preview {
+            write_preview(path, args.preview_size, || placed.iter().copied())?;
+        }
+        let options = write_options(&args);
+        let mut out = create_output(args.output.as_deref(), &args)?;
+        write_shaded(&mut out, &placed, args.output_format, &args, &options)?;
+        out.finish()?;
+        if args.stats {
+            info!("input:\n{}", checked_stats(&mesh, args.weld_epsilon));
+            info!("output:\n{}", checked_stats(&placed, args.weld_epsilon));
+        }
+        return Ok(());
+    }
+    if args.scale_range.is_some()
+        && (args.scale.is_some()
+            || !args.scales.is_empty()
+            || args.scale_xyz.is_some()
+            || args.scale_ratio != 1.0)
+    {
+        warn!("--scale-range overrides the other scale options");
+    }
+    let depth = match matches.value_source("depth") {
+        Some(ValueSource::DefaultValue) if args.area_epsilon.is_some() => MAX_DEPTH as usize,
+        _ => args.depth as usize,
+    };
+    let cli = GrowthParams {
+        depth,
+        branch_depth: args.branch_depth.map(|distribution| match distribution {
+            DepthDistribution::Uniform => BranchDepth::Uniform,
+            DepthDistribution::Poisson => BranchDepth::Poisson {
+                mean: args.branch_depth_mean,
+            },
+        }),
+        num_children: args.children,
+        children_schedule: args.children_per_depth.clone(),
+        growth_probability: args.growth_probability,
+        child_scale: args
+            .scale_xyz
+            .unwrap_or(Vector3::repeat(args.scale.unwrap_or(0.5))),
+        scale_ratio: args.scale_ratio,
+        scale_schedule: args.scales.clone(),
+        scale_range: args.scale_range,
+        scale_to_area: args.scale_to_area,
+        scale_noise: args.scale_noise,
+        max_triangles: args.max_triangles,
+        deadline: args.time_limit.map(|limit| start + limit),
+        traversal: args.traversal,
+        area_epsilon: args.area_epsilon,
+        placement: args.placement,
+        min_distance: args.min_distance,
+        jitter: args.jitter,
+        growth_mode: args.growth_mode,
+        direction: args.direction,
+        orient_up: args.orient_up,
+        rotate_children: args.rotate_children,
+        spread_angle: args.spread_angle,
+        lift: args.lift,
+        normal_filter: args.normal_filter.map(|direction| NormalFilter {
+            direction,
+            min_cos: args.normal_threshold,
+        }),
+        region: args.region_box.map(|(min, max)| Region { min, max }),
+        grow_mask: marks.chosen,
+        tangents: marks.tangents,
+        selection_weight: args.selection_weight,
+        bias: args.bias_direction.map(|direction| Bias {
+            direction,
+            strength: args.bias_strength,
+        }),
+        min_triangle_area: args.min_triangle_area,
+        min_feature_size: args.min_feature_size,
+        sample_tolerance: args.sample_tolerance,
+        avoid_overlap: args.avoid_overlap,
+        grow_on: args.grow_on,
+        grow_on_previous: args.grow_on_previous,
+        child_weights: args.child_weights.clone(),
+        child_choice: args.child_choice,
+    };
+    let mut params = match &args.params {
+        Some(path) => {
+            let file =
+                load_params(path).with_context(|| format!("failed to read {}", path.display()))?;
+            override_params(file, cli, &matches)
+        }
+        None => cli,
+    };
+    if let Some(spacing) = args.cover {
+        cover(&mut params, spacing, &mesh, children)?;
+    }
+    if let Some(generation) = args.only_generation {
+        ensure!(
+            generation <= params.depth,
+            "--only-generation {generation} is deeper than --depth {}",
+            params.depth
+        );
+    }
+    ensure!(
+        children.len() == 1
+            || params.child_weights.is_empty()
+            || params.child_weights.len() == children.len(),
+        "{} child weights for {} child meshes, expected one each",
+        params.child_weights.len(),
+        children.len()
+    );
+    match (params.placement, params.min_distance > 0.0) {
+        (Placement::Poisson, false) => {
+            warn!("--placement poisson without --min-distance places children like random")
+        }
+        (Placement::Centroid | Placement::Random, true) => {
+            warn!("--min-distance only applies to --placement poisson")
+        }
+        _ => {}
+    }
+    if params.child_choice == ChildChoice::ByGeneration && !params.child_weights.is_empty() {
+        warn!("--child-weights are ignored when choosing children by generation");
+    }
+    if params.grow_on_previous && params.growth_probability.is_some() {
+        warn!("--grow-on-previous changes nothing with --growth-probability");
+    } else if params.grow_on_previous && params.grow_on == GrowOn::Base {
+        warn!("--grow-on-previous changes nothing with --grow-on base");
+    }
+    if args.provenance {
+        let hash = provenance(&params, seed, std::iter::once(&mesh).chain(children))?;
+        info!("provenance: {hash}");
+        args.header_text = Some(format!("fractulate {hash}"));
+    }
+    let base_triangles = mesh.len();
+    // with children of different sizes the count can only be an upper bound
+    let child_triangles = children.iter().map(|child| child.len()).max().unwrap_or(0);
+    let uneven = children.iter().any(|child| child.len() != child_triangles);
+    if args.dry_run {
+        let mut count = fractulate::triangle_count(base_triangles, child_triangles, &params);
+        if args.mirror.is_some() {
+            count = base_triangles.saturating_add((count - base_triangles).saturating_mul(2));
+        }
+        if args.no_base {
+            count -= base_triangles;
+        }
+        let mib = |bytes: usize| bytes as f64 / (1024.0 * 1024.0);
+        if params.growth_probability.is_some() {
+            info!("expected output triangles: {count}");
+        } else if uneven
+            || args.only_generation.is_some()
+            || params.branch_depth.is_some()
+            || params.min_triangle_area > 0.0
+            || params.min_feature_size > 0.0
+            || params.avoid_overlap
+            || params.placement == Placement::Poisson
+            || params.area_epsilon.is_some()
+        {
+            info!("output triangles: at most {count}");
+        } else {
+            info!("output triangles: {count}");
+        }
+        info!(
+            "memory: ~{:.1} MiB",
+            mib(count.saturating_mul(size_of::<Triangle>()))
+        );
+        info!(
+            "binary stl: ~{:.1} MiB",
+            mib(count.saturating_mul(50).saturating_add(84))
+        );
+        return Ok(());
+    }
+    let outputs = match args
+        .out_pattern
+        .as_ref()
+        .filter(|_| args.variants.is_some())
+    {
+        Some(pattern) => (0..args.variants.unwrap_or(1))
+            .map(|i| {
+                let seed = seed.wrapping_add(i.into());
+                let path = PathBuf::from(pattern.replace("{}", &seed.to_string()));
+                // each variant gets the whole --time-limit to itself
+                let start = Instant::now();
+                let params = GrowthParams {
+                    deadline: args.time_limit.map(|limit| start + limit),
+                    ..params.clone()
+                };
+                let (stats, fallbacks) = grow_and_write(
+                    &args,
+                    &mesh,
+                    children,
+                    &params,
+                    seed,
+                    input_scale,
+                    Some(&path),
+                    start,
+                )?;
+                Ok((path.display().to_string(), stats, fallbacks))
+            })
+            .collect::<Result<Vec<_>>>()?,
+        None => {
+            let output = args.output.as_deref();
+            let (stats, fallbacks) = grow_and_write(
+                &args,
+                &mesh,
+                children,
+                &params,
+                seed,
+                input_scale,
+                output,
+                start,
+            )?;
+            vec![("output".to_string(), stats, fallbacks)]
+        }
+    };
+    if args.stats {
+        info!("input:\n{}", checked_stats(&mesh, args.weld_epsilon));
+        info!(
+            "picking input triangles to grow on by {} search",
+            Search::for_len(mesh.len())
+        );
+        if let Some(bins) = args.area_histogram {
+            let histogram = AreaHistogram::of(&mesh, bins as usize);
+            info!("input triangle areas:\n{histogram}");
+        }
+        match &args.child[..] {
+            [] => {}
+            [_] => info!("child:\n{}", checked_stats(&children[0], args.weld_epsilon)),
+            paths => {
+                for (path, child) in paths.iter().zip(children) {
+                    let stats = checked_stats(child, args.weld_epsilon);
+                    info!("child {}:\n{stats}", path.display());
+                }
+            }
+        }
+        for (name, stats, fallbacks) in outputs {
+            info!("{name}:\n{stats}");
+            if fallbacks > 0 {
+                info!("{fallbacks} picks landed within --sample-tolerance of the end");
+            }
+        }
+    }
     Ok(())
 }
 
-fn load() -> Result<Vec<[Vector3<f32>; 3]>> {
-    let mut buf = Vec::new();
-    std::io::stdin().read_to_end(&mut buf).unwrap();
-    let stl = read_stl(&mut Cursor::new(buf)).unwrap();
+/// Grow a fractal of `children` on `mesh` from `seed` and write it to `output`, or stdout if
+/// `None`. Returns statistics about the output if --stats asked for them, and how many picks fell
+/// back from rounding while growing it. `input_scale` is what --normalize-input scaled the inputs
+/// by. `start` is when growing started, for --progress.
+#[allow(clippy::too_many_arguments)]
+fn grow_and_write(
+    args: &Args,
+    mesh: &Mesh,
+    children: &[Mesh],
+    params: &GrowthParams,
+    seed: u64,
+    input_scale: f32,
+    output: Option<&Path>,
+    start: Instant,
+) -> Result<(Stats, usize)> {
+    let base_triangles = mesh.len();
+    let child_triangles: Vec<usize> = children.iter().map(|child| child.len()).collect();
+    let largest = child_triangles.iter().copied().max().unwrap_or(0);
+    let (mut instances, fallbacks) =
+        fractulate::instances_with_fallbacks(mesh, children, params, &mut *args.rng.seeded(seed))?;
+    debug!("planned {} copies from seed {seed}", instances.len());
+    let count = fractulate::instance_triangles(base_triangles, &child_triangles, &instances);
+    if args.progress {
+        report_generations(&instances, start);
+    }
+    if let Some(path) = &args.tree_out {
+        write_tree(path, &instances)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+    }
+    if let Some(path) = &args.anchors_out {
+        let anchors: Vec<Vector3<f32>> =
+            fractulate::anchor_points(&instances, params.lift).collect();
+        let mut writer = BufWriter::new(
+            File::create(path).with_context(|| format!("failed to create {}", path.display()))?,
+        );
+        ply::write_points(&mut writer, &anchors, args.ply_ascii)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+    }
+    let unbounded = fractulate::unbounded_triangle_count(base_triangles, largest, params);
+    if params
+        .deadline
+        .is_some_and(|deadline| Instant::now() >= deadline)
+    {
+        let deepest = instances.iter().map(|instance| instance.generation).max();
+        warn!(
+            "stopped growing at {count} triangles, {} generations deep, to stay within \
+             --time-limit",
+            deepest.unwrap_or(0)
+        );
+    } else if params.growth_probability.is_some()
+        || params.branch_depth.is_some()
+        || params.min_triangle_area > 0.0
+        || params.min_feature_size > 0.0
+        || params.avoid_overlap
+        || params.placement == Placement::Poisson
+        || params.area_epsilon.is_some()
+        || child_triangles
+            .iter()
+            .any(|&triangles| triangles != largest)
+    {
+        // the unbounded count is only a guess, but another child not fitting means the budget
+        // probably cut growth short
+        if params
+            .max_triangles
+            .is_some_and(|budget| count + largest > budget)
+        {
+            warn!("stopped growing at {count} triangles to stay within --max-triangles");
+        }
+    } else if count < unbounded {
+        warn!("stopped growing at {count} of {unbounded} triangles to stay within --max-triangles");
+    }
+    let cut_short = params
+        .deadline
+        .is_some_and(|deadline| Instant::now() >= deadline)
+        || params
+            .max_triangles
+            .is_some_and(|budget| count + largest > budget);
+    if params.area_epsilon.is_some() {
+        let deepest = instances.iter().map(|instance| instance.generation).max();
+        let deepest = deepest.unwrap_or(0);
+        if cut_short {
+            info!("grew {deepest} generations deep");
+        } else if deepest < params.depth {
+            info!("grew {deepest} generations deep, where the area settled");
+        } else {
+            warn!("grew all {deepest} generations of --depth without the area settling");
+        }
+    }
+    // other ways of leaving children out would be counted too, and --cover asks for more than fit
+    if params.placement == Placement::Poisson
+        && args.cover.is_none()
+        && params.growth_probability.is_none()
+        && !params.avoid_overlap
+        && params.min_triangle_area == 0.0
+        && params.min_feature_size == 0.0
+        && !cut_short
+    {
+        let crowded = crowded_out(&instances, params);
+        if crowded > 0 {
+            warn!(
+                "left out {crowded} children with no room for them at least --min-distance from \
+                 their siblings"
+            );
+        }
+    }
+    if let Some(factor) = args.explode {
+        fractulate::explode(&mut instances, mesh, children, factor);
+    }
+    let count = match args.mirror {
+        Some(plane) => {
+            fractulate::mirror_children(&mut instances, plane);
+            fractulate::instance_triangles(base_triangles, &child_triangles, &instances)
+        }
+        None => count,
+    };
+    let count = match args.only_generation {
+        Some(generation) => {
+            instances.retain(|instance| instance.generation == generation);
+            fractulate::instance_triangles(base_triangles, &child_triangles, &instances)
+        }
+        None if args.no_base => {
+            instances.retain(|instance| instance.generation != 0);
+            count - base_triangles
+        }
+        None => count,
+    };
+    // copies are placed a vertex at a time rather than a triangle at a time, then expanded back
+    let base_indexed = fractulate::index(mesh);
+    let children_indexed: Vec<_> = children
+        .iter()
+        .map(|child| fractulate::index(child))
+        .collect();
+    let place = |chunk: &[Instance]| -> Mesh {
+        fractulate::instantiate_indexed_with(&base_indexed, &children_indexed, chunk)
+            .triangles()
+            .collect()
+    };
+    // post processing and checking need the whole fractal at once
+    let streaming = !post_processing(args) && !args.check_watertight;
+    check_memory(
+        args,
+        memory_estimate(args, instances.len(), count, streaming),
+    )?;
+    if streaming {
+        let mut measured = Stats::default();
+        if needs_measuring(args) {
+            // generate the fractal once just to measure it, rather than hold it all in memory
+            for chunk in instances.chunks(STREAM_CHUNK) {
+                measured.extend(&place(chunk));
+            }
+        }
+        let transform = output_transform(&measured, args, input_scale);
+        if transform != Matrix4::identity() {
+            for instance in &mut instances {
+                instance.transform = transform.cast::<Real>() * instance.transform;
+            }
+        }
+    }
+    let mut output_stats = Stats::default();
+    let mut progress =
+        (args.progress && log::log_enabled!(Level::Info)).then(|| Progress::new(count));
+    let chunks = instances.chunks(STREAM_CHUNK).map(place).inspect(|chunk| {
+        if args.stats {
+            output_stats.extend(chunk);
+        }
+        if let Some(progress) = &mut progress {
+            progress.advance(chunk.len());
+        }
+    });
+    ensure_not_empty(count)?;
+    let create = || create_output(output, args);
+    // where the CSV still needs writing, once the output's been streamed without keeping it
+    let mut csv_out = args.csv_out.as_deref();
+    let mut preview = args.preview.as_deref();
+    let header = args.header_text.as_deref();
+    if let Some(pattern) = args
+        .out_pattern
+        .as_deref()
+        .filter(|_| args.split_by_generation)
+    {
+        let chunks = |chunk: &[Instance]| {
+            let chunk = place(chunk);
+            if args.stats {
+                output_stats.extend(&chunk);
+            }
+            if let Some(progress) = &mut progress {
+                progress.advance(chunk.len());
+            }
+            chunk
+        };
+        write_generations(
+            args,
+            pattern,
+            &instances,
+            params.depth,
+            chunks,
+            |instances| fractulate::instance_triangles(base_triangles, &child_triangles, instances),
+        )?;
+    } else {
+        let out = match args.output_format {
+            // binary STL can be written as it's generated, so the fractal never needs to fit in
+            // memory
+            Format::Stl if streaming && args.stl_ascii => {
+                let mut out = create()?;
+                stl::write_ascii(&mut out, header, chunks.flatten())?;
+                out
+            }
+            Format::Stl if streaming => {
+                let mut out = create()?;
+                match args.color_by_depth {
+                    Some(gradient) => {
+                        let colors = fractulate::triangle_generations(
+                            base_triangles,
+                            &child_triangles,
+                            &instances,
+                        )
+                        .map(|generation| depth_color(gradient, generation, params.depth));
+                        stl::write_colored_iter(
+                            &mut out,
+                            header,
+                            count,
+                            chunks.flatten().zip(colors),
+                        )?
+                    }
+                    None => stl::write_iter(&mut out, header, count, chunks.flatten())?,
+                }
+                out
+            }
+            format if streaming && args.compact => {
+                let mut bounds = Stats::default();
+                for chunk in instances.chunks(STREAM_CHUNK) {
+                    bounds.extend(&place(chunk));
+                }
+                let bounds = bounds.bounds.ok_or(FractulateError::EmptyResult)?;
+                let compact = CompactMesh::new(bounds, chunks.flatten());
+                debug!(
+                    "gathered {} vertices, each within {} of where it was",
+                    compact.vertices().len(),
+                    compact.max_error()
+                );
+                let options = write_options(args);
+                let mut out = create()?;
+                fractulate::format::write_compact(&mut out, &compact, format, &options)?;
+                out
+            }
+            format => {
+                let options = write_options(args);
+                let mesh = post_process(chunks.flatten().collect(), args, seed, input_scale);
+                ensure_not_empty(mesh.len())?;
+                if !streaming && args.stats {
+                    output_stats = checked_stats(&mesh, args.weld_epsilon);
+                }
+                if args.check_watertight {
+                    report_watertightness(&mesh, args.weld_epsilon);
+                }
+                if let Some(path) = csv_out.take() {
+                    write_csv(path, mesh.iter().copied())?;
+                }
+                if let Some(path) = preview.take() {
+                    write_preview(path, args.preview_size, || mesh.iter().copied())?;
+                }
+                let mut out = create()?;
+                match args.color_by_depth {
+                    Some(gradient) => {
+                        let colors: Vec<Rgb> = fractulate::triangle_generations(
+                            base_triangles,
+                            &child_triangles,
+                            &instances,
+                        )
+                        .map(|generation| depth_color(gradient, generation, params.depth))
+                        .collect();
+                        fractulate::format::write_colored(
+                            &mut out, &mesh, &colors, format, &options,
+                        )?
+                    }
+                    None => write_shaded(&mut out, &mesh, format, args, &options)?,
+                }
+                out
+            }
+        };
+        out.finish()?;
+    }
+    if let Some(path) = csv_out {
+        let triangles = instances.chunks(STREAM_CHUNK).flat_map(place);
+        write_csv(path, triangles)?;
+    }
+    if let Some(path) = preview {
+        let triangles = || instances.chunks(STREAM_CHUNK).flat_map(place);
+        write_preview(path, args.preview_size, triangles)?;
+    }
+
+    if args.stats && streaming {
+        // the grids need the bounds of the whole fractal, which are only known now, as does a
+        // sphere around their center
+        if let Some((min, max)) = output_stats.bounds {
+            let mut counter = BoxCounter::new((min, max));
+            let center = (min + max) / 2.0;
+            let mut radius: f32 = 0.0;
+            for chunk in instances.chunks(STREAM_CHUNK) {
+                let chunk = place(chunk);
+                counter.extend(&chunk);
+                for v in chunk.iter().flatten() {
+                    radius = radius.max((v - center).norm());
+                }
+            }
+            output_stats.dimension = counter.dimension();
+            output_stats.sphere = Some(Sphere { center, radius });
+        }
+    }
+    Ok((output_stats, fallbacks))
+}
 
-    let mesh = stl
-        .faces
+/// How many children `instances` are short of every copy that grows children having as many as
+/// `params` allows, for reporting how many --placement poisson found no room for.
+fn crowded_out(instances: &[Instance], params: &GrowthParams) -> usize {
+    let mut grown = vec![0; instances.len()];
+    for origin in instances.iter().filter_map(|instance| instance.origin) {
+        grown[origin.parent] += 1;
+    }
+    instances
         .iter()
-        .map(|face| {
-            face.vertices.map(|v| {
-                let ret: [f32; 3] = stl.vertices[v].into();
-                let ret: Vector3<f32> = ret.into();
-                ret
+        .zip(grown)
+        .filter(|(instance, _)| instance.generation < instance.max_generation)
+        .map(|(instance, grown)| {
+            let allowed =
+                params.children_of(instance.generation) * params.direction.children_per_anchor();
+            allowed.saturating_sub(grown)
+        })
+        .sum()
+}
+
+/// Send messages to stderr, as many as --quiet and --verbose ask for. Reports asked for, like
+/// --stats, are info, so only --quiet hides them.
+fn init_logging(args: &Args) {
+    let level = match (args.quiet, args.verbose) {
+        (true, _) => LevelFilter::Error,
+        (false, 0) => LevelFilter::Info,
+        (false, 1) => LevelFilter::Debug,
+        (false, _) => LevelFilter::Trace,
+    };
+    env_logger::Builder::new()
+        .filter_level(level)
+        .format(|f, record| match record.level() {
+            Level::Info => writeln!(f, "{}", record.args()),
+            Level::Warn => writeln!(f, "warning: {}", record.args()),
+            level => writeln!(f, "{}: {}", level.as_str().to_lowercase(), record.args()),
+        })
+        .init();
+}
+
+/// Where --instance-file puts a copy of the input.
+#[derive(Debug, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Pose {
+    translation: Vector3<f32>,
+    /// Degrees about x, then the rotated y, then the twice rotated z.
+    rotation: Vector3<f32>,
+    scale: f32,
+}
+
+impl Default for Pose {
+    fn default() -> Self {
+        Pose {
+            translation: Vector3::zeros(),
+            rotation: Vector3::zeros(),
+            scale: 1.0,
+        }
+    }
+}
+
+impl Pose {
+    fn transform(&self) -> Matrix4<f32> {
+        let [x, y, z] = self.rotation.map(f32::to_radians).into();
+        let rotate = Matrix4::from_axis_angle(&Vector3::x_axis(), x)
+            * Matrix4::from_axis_angle(&Vector3::y_axis(), y)
+            * Matrix4::from_axis_angle(&Vector3::z_axis(), z);
+        Matrix4::new_translation(&self.translation) * rotate * Matrix4::new_scaling(self.scale)
+    }
+}
+
+/// Poses from an --instance-file.
+fn load_poses(path: &Path) -> Result<Vec<Pose>> {
+    let extension = path.extension().and_then(|e| e.to_str());
+    ensure!(
+        matches!(extension, Some("json" | "csv")),
+        "expected a .json or .csv file"
+    );
+    let text = std::fs::read_to_string(path)?;
+    if extension == Some("json") {
+        return Ok(serde_json::from_str(&text)?);
+    }
+    text.lines()
+        .enumerate()
+        .skip(1)
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let row = line
+                .split(',')
+                .map(|c| c.trim().parse())
+                .collect::<Result<Vec<f32>, _>>()
+                .with_context(|| format!("line {}", i + 1))?;
+            let [tx, ty, tz, rx, ry, rz, scale] = row[..] else {
+                bail!("line {} has {} columns, expected 7", i + 1, row.len());
+            };
+            Ok(Pose {
+                translation: Vector3::new(tx, ty, tz),
+                rotation: Vector3::new(rx, ry, rz),
+                scale,
             })
         })
-        .collect();
-    Ok(mesh)
+        .collect()
 }
 
-fn save(mesh: Vec<[Vector3<f32>; 3]>) -> Result<()> {
-    let stl_io_mesh = mesh.into_iter().map(|triangle| {
-        let vertices = triangle.map(|v| Vertex::new(v.into()));
-        Triangle {
-            normal: Normal::new(get_normal(&triangle).into()),
-            vertices,
+/// Growth options from a --params file.
+fn load_params(path: &Path) -> Result<GrowthParams> {
+    let extension = path.extension().and_then(|e| e.to_str());
+    ensure!(
+        matches!(extension, Some("json" | "toml")),
+        "expected a .json or .toml file"
+    );
+    let text = std::fs::read_to_string(path)?;
+    let mut params: GrowthParams = if extension == Some("toml") {
+        toml::from_str(&text)?
+    } else {
+        serde_json::from_str(&text)?
+    };
+    ensure!(
+        params.depth <= MAX_DEPTH as usize,
+        "depth must be at most {MAX_DEPTH}"
+    );
+    ensure!(
+        params
+            .growth_probability
+            .is_none_or(|p| (0.0..=1.0).contains(&p)),
+        "growth_probability must be between 0 and 1"
+    );
+    let positive = |scale: f32| scale.is_finite() && scale > 0.0;
+    ensure!(
+        params.child_scale.iter().all(|&scale| positive(scale))
+            && positive(params.scale_ratio)
+            && params.scale_schedule.iter().all(|&scale| positive(scale))
+            && params
+                .scale_range
+                .is_none_or(|(min, max)| positive(min) && positive(max) && min <= max)
+            && params.scale_to_area.is_none_or(positive),
+        "scales must be positive, and scale_range's min no more than its max"
+    );
+    ensure!(
+        params.min_distance.is_finite() && params.min_distance >= 0.0,
+        "min_distance must be a non-negative number"
+    );
+    ensure!(
+        (0.0..1.0).contains(&params.sample_tolerance),
+        "sample_tolerance must be at least 0 and less than 1"
+    );
+    ensure!(
+        params.jitter.is_finite() && params.jitter >= 0.0,
+        "jitter must be a non-negative number"
+    );
+    ensure!(
+        params.min_triangle_area.is_finite() && params.min_triangle_area >= 0.0,
+        "min_triangle_area must be a non-negative number"
+    );
+    if let Some(filter) = &params.normal_filter {
+        ensure!(
+            (-1.0..=1.0).contains(&filter.min_cos),
+            "normal filter min_cos must be between -1 and 1"
+        );
+    }
+    ensure!(
+        params.min_feature_size.is_finite() && params.min_feature_size >= 0.0,
+        "min_feature_size must be a non-negative number"
+    );
+    if let Some(epsilon) = params.area_epsilon {
+        ensure!(
+            (0.0..=1.0).contains(&epsilon),
+            "area_epsilon must be between 0 and 1"
+        );
+    }
+    ensure!(
+        (0.0..1.0).contains(&params.scale_noise),
+        "scale_noise must be at least 0 and less than 1"
+    );
+    ensure!(
+        (0.0..=180.0).contains(&params.spread_angle),
+        "spread_angle must be between 0 and 180 degrees"
+    );
+    ensure!(
+        params
+            .child_weights
+            .iter()
+            .all(|&weight| weight.is_finite() && weight >= 0.0),
+        "child_weights must be non-negative numbers"
+    );
+    if let Some(BranchDepth::Poisson { mean }) = params.branch_depth {
+        ensure!(
+            mean.is_finite() && mean >= 0.0,
+            "branch depth mean must be a non-negative number"
+        );
+    }
+    if let Some(region) = &params.region {
+        ensure!(
+            region
+                .min
+                .iter()
+                .zip(&region.max)
+                .all(|(min, max)| min <= max),
+            "region min can't be more than its max"
+        );
+    }
+    if let Some(bias) = &params.bias {
+        ensure!(
+            (0.0..=1.0).contains(&bias.strength),
+            "bias strength must be between 0 and 1"
+        );
+    }
+    // directions are normalized as they are on the command line
+    let directions = params
+        .normal_filter
+        .iter_mut()
+        .map(|filter| &mut filter.direction)
+        .chain(params.bias.iter_mut().map(|bias| &mut bias.direction))
+        .chain(params.orient_up.iter_mut());
+    for direction in directions {
+        *direction = direction
+            .try_normalize(0.0)
+            .ok_or_else(|| anyhow!("direction can't be zero"))?;
+    }
+    Ok(params)
+}
+
+/// `file` with each option given on the command line replaced by its value in `cli`.
+fn override_params(
+    mut file: GrowthParams,
+    cli: GrowthParams,
+    matches: &ArgMatches,
+) -> GrowthParams {
+    let given = |ids: &[&str]| {
+        ids.iter().any(|id| {
+            matches
+                .value_source(id)
+                .is_some_and(|source| source != ValueSource::DefaultValue)
+        })
+    };
+    // fields that decide the same thing are replaced together, so --children replaces a
+    // growth_probability from the file and --scale a scale_schedule
+    macro_rules! replace {
+        ($($($field:ident),+ <- $($id:literal),+;)*) => {
+            $(if given(&[$($id),+]) {
+                $(file.$field = cli.$field;)+
+            })*
+        };
+    }
+    replace! {
+        depth <- "depth";
+        branch_depth <- "branch_depth";
+        num_children, children_schedule, growth_probability
+            <- "children", "children_per_depth", "growth_probability";
+        child_scale, scale_schedule <- "scale", "scales", "scale_xyz";
+        scale_ratio <- "scale_ratio";
+        scale_range <- "scale_range";
+        scale_to_area <- "scale_to_area";
+        scale_noise <- "scale_noise";
+        max_triangles <- "max_triangles";
+        traversal <- "traversal";
+        area_epsilon <- "area_epsilon";
+        placement <- "placement";
+        min_distance <- "min_distance";
+        jitter <- "jitter";
+        growth_mode <- "growth_mode";
+        direction <- "direction";
+        orient_up <- "orient_up";
+        rotate_children <- "rotate_children";
+        spread_angle <- "spread_angle";
+        lift <- "lift";
+        normal_filter <- "normal_filter";
+        region <- "region_box";
+        selection_weight <- "selection_weight";
+        bias <- "bias_direction";
+        min_triangle_area <- "min_triangle_area";
+        min_feature_size <- "min_feature_size";
+        sample_tolerance <- "sample_tolerance";
+        avoid_overlap <- "avoid_overlap";
+        grow_on <- "grow_on";
+        grow_on_previous <- "grow_on_previous";
+        child_weights <- "child_weights";
+        child_choice <- "child_choice";
+    }
+    file.deadline = cli.deadline;
+    file.grow_mask = cli.grow_mask;
+    file.tangents = cli.tangents;
+    file
+}
+
+fn write_csv(path: &Path, triangles: impl IntoIterator<Item = Triangle>) -> Result<()> {
+    File::create(path)
+        .map_err(anyhow::Error::from)
+        .and_then(|mut file| csv::write_iter(&mut file, triangles))
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Draw the triangles from `triangles` to `path` for --preview. Called twice, once to frame the
+/// picture around them and once to draw them, so they can be generated rather than kept.
+fn write_preview<I: IntoIterator<Item = Triangle>>(
+    path: &Path,
+    size: u32,
+    triangles: impl Fn() -> I,
+) -> Result<()> {
+    let mut bounds = Stats::default();
+    for triangle in triangles() {
+        bounds.add(&triangle);
+    }
+    let Some(bounds) = bounds.bounds else {
+        return Ok(());
+    };
+    let mut preview = Preview::new(size as usize, bounds);
+    for triangle in triangles() {
+        preview.draw(&triangle);
+    }
+    File::create(path)
+        .and_then(|file| preview.write_png(BufWriter::new(file)))
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// One copy in the JSON written by --tree-out.
+#[derive(Serialize)]
+struct GrowthNode {
+    /// Index of the copy this grew on, `None` for the input.
+    parent: Option<usize>,
+    /// Which of the parent's triangles this grew on.
+    triangle: Option<usize>,
+    generation: usize,
+    /// Row major.
+    transform: [[Real; 4]; 4],
+}
+
+fn write_tree(path: &Path, instances: &[Instance]) -> Result<()> {
+    let mut writer = std::io::BufWriter::new(File::create(path)?);
+    writer.write_all(b"[")?;
+    for (i, instance) in instances.iter().enumerate() {
+        if i > 0 {
+            writer.write_all(b",")?;
         }
-    });
-    write_stl(&mut std::io::stdout(), stl_io_mesh)?;
+        let node = GrowthNode {
+            parent: instance.origin.map(|origin| origin.parent),
+            triangle: instance.origin.map(|origin| origin.triangle),
+            generation: instance.generation,
+            transform: std::array::from_fn(|row| {
+                std::array::from_fn(|column| instance.transform[(row, column)])
+            }),
+        };
+        serde_json::to_writer(&mut writer, &node)?;
+    }
+    writer.write_all(b"]\n")?;
+    writer.flush()?;
     Ok(())
 }
 
-fn main_transform(triangles: Vec<[Vector3<f32>; 3]>) -> Vec<[Vector3<f32>; 3]> {
-    let mut rng = rand_xoshiro::Xoshiro256StarStar::seed_from_u64(0);
-    let mut ret = triangles.clone();
-    ret.extend(growths(&mut rng, &triangles, 2));
+/// Print how many copies each generation grew, and how long that took.
+fn report_generations(instances: &[Instance], start: Instant) {
+    let mut copies = Vec::new();
+    for instance in instances {
+        if copies.len() <= instance.generation {
+            copies.resize(instance.generation + 1, 0);
+        }
+        copies[instance.generation] += 1;
+    }
+    for (generation, copies) in copies.iter().enumerate().skip(1) {
+        info!("generation {generation}: {copies} copies");
+    }
+    info!(
+        "grew {} copies in {:.1}s",
+        instances.len(),
+        start.elapsed().as_secs_f64()
+    );
+}
+
+/// Running count of triangles placed, printed over itself on one line of stderr.
+struct Progress {
+    total: usize,
+    done: usize,
+    last_report: Option<Instant>,
+}
+
+impl Progress {
+    /// Time between reports, so a fast run isn't slowed down by printing.
+    const INTERVAL: Duration = Duration::from_millis(200);
+
+    fn new(total: usize) -> Self {
+        Progress {
+            total,
+            done: 0,
+            last_report: None,
+        }
+    }
+
+    fn advance(&mut self, triangles: usize) {
+        self.done += triangles;
+        let finished = self.done >= self.total;
+        if finished
+            || self
+                .last_report
+                .is_none_or(|last| last.elapsed() >= Self::INTERVAL)
+        {
+            eprint!("\rplaced {} of {} triangles", self.done, self.total);
+            if finished {
+                eprintln!();
+            }
+            self.last_report = Some(Instant::now());
+        }
+    }
+}
+
+/// Set `params` up for --cover: a single generation of children at least `spacing` apart, as many
+/// as could possibly fit on `mesh`, each scaled so its footprint is `spacing` across and
+/// neighbors about meet. Placement stops where there's no room left, so the cover is only
+/// approximate, with gaps where the random placement left too little room for another child and
+/// overlaps where footprints aren't square.
+fn cover(params: &mut GrowthParams, spacing: f32, mesh: &Mesh, children: &[Mesh]) -> Result<()> {
+    let width = children
+        .iter()
+        .filter_map(|child| fractulate::bounding_box(child))
+        .map(|(min, max)| (max - min).xy().max())
+        .fold(0.0, f32::max);
+    ensure!(
+        width > 0.0,
+        "--cover needs a child with some width along its x or y axis"
+    );
+    let area: f32 = mesh.iter().map(fractulate::area).sum();
+    // hexagonal packing is the densest there is, so this never runs out before the room does
+    let count = (area * 2.0 / (3f32.sqrt() * spacing * spacing)).ceil() as usize;
+    let scale = spacing / width;
+    debug!("covering the input with up to {count} children scaled by {scale}");
+    params.depth = 1;
+    params.branch_depth = None;
+    params.num_children = count;
+    params.children_schedule.clear();
+    params.growth_probability = None;
+    params.placement = Placement::Poisson;
+    params.min_distance = spacing;
+    params.scale_range = Some((scale, scale));
+    Ok(())
+}
+
+/// A short hash of everything that decides what grows, for --provenance: the growth options as
+/// they'd be saved to a params file, the seed, and the triangles of `meshes`. The same on every
+/// platform and run, so it can be compared with a later run's.
+fn provenance<'a>(
+    params: &GrowthParams,
+    seed: u64,
+    meshes: impl IntoIterator<Item = &'a Mesh>,
+) -> Result<String> {
+    // 64 bit FNV-1a, since std's hashers are free to change between releases
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut add = |bytes: &[u8]| {
+        for &byte in bytes {
+            hash = (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    };
+    add(&serde_json::to_vec(params)?);
+    add(&seed.to_le_bytes());
+    for mesh in meshes {
+        add(&(mesh.len() as u64).to_le_bytes());
+        for c in mesh.iter().flatten().flatten() {
+            add(&c.to_le_bytes());
+        }
+    }
+    Ok(format!("{hash:016x}"))
+}
+
+/// Stats of `mesh`, including whether it's watertight once vertices are merged as with
+/// --weld-epsilon.
+fn checked_stats(mesh: &Mesh, weld_epsilon: f32) -> Stats {
+    let mut ret = Stats::of(mesh);
+    ret.watertight = Some(
+        fractulate::weld(mesh, weld_epsilon)
+            .watertightness()
+            .is_watertight(),
+    );
+    ret.sphere = fractulate::bounding_sphere(mesh);
+    ret.dimension = ret.bounds.and_then(|bounds| {
+        let mut counter = BoxCounter::new(bounds);
+        counter.extend(mesh);
+        counter.dimension()
+    });
     ret
 }
 
-fn growths<R: Rng>(
-    rng: &mut R,
-    base_model: &[[Vector3<f32>; 3]],
-    depth: usize,
-) -> Vec<[Vector3<f32>; 3]> {
-    let Some(next_depth) = depth.checked_sub(1) else {
-        return Vec::new();
+fn report_watertightness(mesh: &Mesh, weld_epsilon: f32) {
+    let check = fractulate::weld(mesh, weld_epsilon).watertightness();
+    if check.is_watertight() {
+        info!("output is watertight");
+    } else {
+        warn!(
+            "output is not watertight, {} boundary edges and {} non-manifold edges",
+            check.boundary_edges, check.non_manifold_edges
+        );
+    }
+}
+
+/// Rough bytes it takes to build and write `triangles` of output from `instances` copies, for
+/// --max-memory. Binary STL, and ascii STL or OBJ and PLY under --compact, are written as they're
+/// generated unless `streaming` is false, otherwise the output is held whole, and merging its
+/// vertices for post processing or for formats sharing them takes another index per corner and up
+/// to a vertex for each.
+fn memory_estimate(args: &Args, instances: usize, triangles: usize, streaming: bool) -> usize {
+    let copies = instances.saturating_mul(size_of::<Instance>());
+    let stl = args.output_format == Format::Stl;
+    let output = match streaming && (stl || args.compact) {
+        // a compact mesh holds three short coordinates a vertex and three indices a face
+        true if args.compact => size_of::<[u16; 3]>() + size_of::<[u32; 3]>(),
+        true => 0,
+        false => size_of::<Triangle>(),
+    };
+    let welded = match welds(args) || (!stl && !args.compact) {
+        true => size_of::<[usize; 3]>() + size_of::<Triangle>(),
+        false => 0,
     };
+    // every triangle becomes up to three struts of eight triangles
+    let struts = match args.wireframe {
+        Some(_) => 3 * 8 * size_of::<Triangle>(),
+        None => 0,
+    };
+    copies.saturating_add(triangles.saturating_mul(output + welded + struts))
+}
 
-    let num_children = 5;
-    let child_scale = 0.5;
+/// Fail if `bytes` is more than --max-memory allows.
+fn check_memory(args: &Args, bytes: usize) -> Result<()> {
+    let Some(limit) = args.max_memory else {
+        return Ok(());
+    };
+    let mib = bytes as f64 / (1024.0 * 1024.0);
+    ensure!(
+        mib <= limit as f64,
+        "writing this would take about {mib:.0} MiB of memory, more than --max-memory {limit}; \
+         lower --depth or --max-triangles to fit"
+    );
+    Ok(())
+}
 
-    let mut ret = Vec::new();
+/// Post processing that needs the whole fractal, and so rules out streaming.
+fn post_processing(args: &Args) -> bool {
+    welds(args)
+        || args.prune_hidden
+        || args.twist.is_some()
+        || args.orient_output
+        || args.wireframe.is_some()
+}
+
+/// Whether post processing works on the output with its vertices merged.
+fn welds(args: &Args) -> bool {
+    args.dedup_triangles
+        || args.smooth > 0
+        || args.displace.is_some()
+        || args.decimate.is_some()
+        || args.merge_coplanar.is_some()
+}
 
-    for _ in 0..num_children {
-        let triangle = select(rng, base_model);
-        let transformation = place_on_triangle(triangle) * Matrix4::new_scaling(child_scale);
-        ret.extend(transform(base_model.to_vec(), transformation));
-        ret.extend(transform(
-            growths(rng, base_model, next_depth),
-            transformation,
+/// Scale `mesh` and `children` together so the diagonal of `mesh`'s bounding box is 1, returning
+/// what they were scaled by, or 1 if `mesh` has no size to scale.
+fn normalize(mesh: &mut Mesh, children: &mut [Mesh]) -> f32 {
+    match fractulate::bounding_box(mesh).map(|(min, max)| (max - min).norm()) {
+        Some(diagonal) if diagonal > 0.0 => {
+            let scaling = Matrix4::new_scaling(diagonal.recip());
+            for mesh in std::iter::once(mesh).chain(children) {
+                mesh.transform(&scaling);
+            }
+            debug!("scaled the input by {} to normalize it", diagonal.recip());
+            diagonal.recip()
+        }
+        _ => {
+            warn!("the input has no size, so can't be normalized");
+            1.0
+        }
+    }
+}
+
+/// Whether the output transform depends on the size or position of the fractal.
+fn needs_measuring(args: &Args) -> bool {
+    args.fit_size.is_some() || center(args).is_some() || args.max_dimension.is_some()
+}
+
+/// What to put on the origin, if anything.
+fn center(args: &Args) -> Option<Center> {
+    args.center_on.or(args.center.then_some(Center::Bbox))
+}
+
+/// Moves the finished fractal, measured as `stats`, into its final place. Fits, or undoes
+/// --normalize-input's `input_scale`, then centers, scales, rotates, shrinks to --max-dimension
+/// and translates, in that order. `stats` is only used if [`needs_measuring`].
+fn output_transform(stats: &Stats, args: &Args, input_scale: f32) -> Matrix4<f32> {
+    let fit = match (args.fit_size, stats.bounds) {
+        (Some(size), Some(bounds)) => fractulate::fit_scaling(bounds, size, args.fit_stretch),
+        _ => Matrix4::new_scaling(input_scale.recip()),
+    };
+    let center = match center(args) {
+        None => None,
+        Some(Center::Bbox) => stats.bounds.map(|(min, max)| (min + max) / 2.0),
+        Some(Center::Centroid) => stats.centroid(),
+    };
+    let center = match center {
+        Some(center) => Matrix4::new_translation(&-fit.transform_vector(&center)),
+        None => Matrix4::identity(),
+    };
+    let scale = Matrix4::new_scaling(args.scale_output.unwrap_or(1.0));
+    let rotate = match args.rotate_euler {
+        Some(degrees) => {
+            let [x, y, z] = degrees.map(f32::to_radians).into();
+            Matrix4::from_axis_angle(&Vector3::x_axis(), x)
+                * Matrix4::from_axis_angle(&Vector3::y_axis(), y)
+                * Matrix4::from_axis_angle(&Vector3::z_axis(), z)
+        }
+        None => Matrix4::identity(),
+    };
+    let placed = rotate * scale * center * fit;
+    let shrink = match (args.max_dimension, stats.bounds) {
+        (Some(limit), Some((min, max))) => {
+            // the box around the moved corners, which holds the moved box
+            let corners = (0..8).map(|i| {
+                let corner = Vector3::new(
+                    if i & 1 == 0 { min.x } else { max.x },
+                    if i & 2 == 0 { min.y } else { max.y },
+                    if i & 4 == 0 { min.z } else { max.z },
+                );
+                placed.transform_point(&corner.into()).coords
+            });
+            let (low, high) = corners.fold(
+                (
+                    Vector3::repeat(f32::INFINITY),
+                    Vector3::repeat(f32::NEG_INFINITY),
+                ),
+                |(low, high), p| (low.inf(&p), high.sup(&p)),
+            );
+            let largest = (high - low).max();
+            if largest > limit {
+                info!(
+                    "shrank the output by {} to fit --max-dimension",
+                    limit / largest
+                );
+                Matrix4::new_scaling(limit / largest)
+            } else {
+                Matrix4::identity()
+            }
+        }
+        _ => Matrix4::identity(),
+    };
+    let translate = Matrix4::new_translation(&args.translate.unwrap_or_default());
+    translate * shrink * placed
+}
+
+fn post_process(mut mesh: Mesh, args: &Args, seed: u64, input_scale: f32) -> Mesh {
+    if post_processing(args) {
+        if welds(args) {
+            let mut welded = fractulate::weld(&mesh, args.weld_epsilon);
+            if args.dedup_triangles {
+                let removed = welded.dedup_faces();
+                info!("removed {removed} duplicate triangles");
+            }
+            fractulate::smooth(&mut welded, args.smooth, args.smooth_lambda);
+            if let Some(amplitude) = args.displace {
+                // fold the seed in half rather than truncate, so seeds differing only in their high
+                // bits still displace differently
+                let seed = (seed ^ seed >> 32) as u32;
+                fractulate::displace(&mut welded, amplitude, args.displace_freq, seed);
+            }
+            if let Some(target) = args.decimate {
+                welded = fractulate::decimate(&welded, target);
+            }
+            if let Some(epsilon) = args.merge_coplanar {
+                welded = fractulate::merge_coplanar(&welded, epsilon);
+            }
+            mesh = welded.triangles().collect();
+        }
+        if args.prune_hidden {
+            mesh = fractulate::prune_hidden(&mesh, args.prune_samples as usize);
+        }
+        if let Some(degrees) = args.twist {
+            mesh.twist(args.twist_axis, degrees.to_radians());
+        }
+        if args.orient_output {
+            let flipped = fractulate::orient(&mut mesh);
+            debug!("flipped {flipped} output triangles to face outward");
+        }
+        if let Some(radius) = args.wireframe {
+            let indexed = match args.wireframe_once {
+                true => fractulate::weld(&mesh, args.weld_epsilon),
+                false => fractulate::index(&mesh),
+            };
+            mesh = fractulate::wireframe(&indexed, radius, args.wireframe_once);
+            debug!("made {} triangles of struts", mesh.len());
+        }
+        let stats = if needs_measuring(args) {
+            Stats::of(&mesh)
+        } else {
+            Stats::default()
+        };
+        let transform = output_transform(&stats, args, input_scale);
+        if transform != Matrix4::identity() {
+            mesh.transform(&transform);
+        }
+    }
+    mesh
+}
+
+/// Check every coordinate of `mesh` is finite, since one NaN spreads through the whole fractal.
+/// Triangles that aren't are removed if `drop` is set, otherwise they're an error. `name` is what
+/// messages call `mesh`.
+fn drop_invalid(mesh: &mut Mesh, marks: &mut Marks, name: &str, drop: bool) -> Result<()> {
+    let finite = |triangle: &Triangle| triangle.iter().flatten().all(|c| c.is_finite());
+    if !drop {
+        return match mesh.iter().position(|triangle| !finite(triangle)) {
+            Some(i) => Err(anyhow!(
+                "triangle {i} of the {name} has a NaN or infinite coordinate, pass --drop-invalid \
+                 to skip such triangles"
+            )),
+            None => Ok(()),
+        };
+    }
+    let before = mesh.len();
+    marks.retain(mesh, finite);
+    let dropped = before - mesh.len();
+    if dropped > 0 {
+        warn!("dropped {dropped} triangles of the {name} with NaN or infinite coordinates");
+    }
+    Ok(())
+}
+
+/// Print what --info says about `mesh`, headed by `name`, along with the STL `header` it was read
+/// with, which is where --provenance leaves its hash.
+fn describe(mesh: &Mesh, header: Option<&str>, name: &str, args: &Args) {
+    let degenerate = mesh.iter().filter(|t| fractulate::is_degenerate(t)).count();
+    let vertices = fractulate::weld(mesh, args.weld_epsilon).vertices.len();
+    println!("{name}:");
+    if let Some(header) = header {
+        println!("  header: {header}");
+    }
+    println!("{}", checked_stats(mesh, args.weld_epsilon));
+    println!("  vertices: {vertices}");
+    println!("  degenerate triangles: {degenerate}");
+}
+
+/// Report to stderr whether `mesh` is fit to grow from for --validate, returning how many kinds of
+/// problem it has. `name` is what the report calls `mesh`.
+fn validate(mesh: &Mesh, name: &str, args: &Args) -> usize {
+    let mut problems = 0;
+    let mut problem = |message: String| {
+        warn!("{name}: {message}");
+        problems += 1;
+    };
+    if mesh.is_empty() {
+        problem("no triangles".to_string());
+        return problems;
+    }
+    let finite = |triangle: &&Triangle| triangle.iter().flatten().all(|c| c.is_finite());
+    let invalid = mesh.len() - mesh.iter().filter(finite).count();
+    if invalid > 0 {
+        problem(format!(
+            "{invalid} triangles with NaN or infinite coordinates"
         ));
     }
+    let degenerate = mesh
+        .iter()
+        .filter(finite)
+        .filter(|triangle| fractulate::is_degenerate(triangle))
+        .count();
+    if degenerate > 0 {
+        problem(format!("{degenerate} degenerate triangles"));
+    }
+    let area: f64 = mesh
+        .iter()
+        .filter(finite)
+        .map(|triangle| fractulate::area(triangle) as f64)
+        .sum();
+    if area <= 0.0 {
+        problem("no area for children to grow on".to_string());
+    }
+    if args.check_watertight && invalid == 0 {
+        let check = fractulate::weld(mesh, args.weld_epsilon).watertightness();
+        if !check.is_watertight() {
+            problem(format!(
+                "not watertight, {} boundary edges and {} non-manifold edges",
+                check.boundary_edges, check.non_manifold_edges
+            ));
+        }
+    }
+    if problems == 0 {
+        info!("{name}: {} triangles, no problems", mesh.len());
+    }
+    problems
+}
 
-    ret
+/// Count the triangles of `mesh` with repeated vertices, refusing it if there are more than
+/// --max-collapsed allows, and removing them if --drop-collapsed. `name` is what messages call
+/// `mesh`.
+fn drop_collapsed(mesh: &mut Mesh, marks: &mut Marks, name: &str, args: &Args) -> Result<()> {
+    let collapsed = |[a, b, c]: &Triangle| a == b || b == c || c == a;
+    let count = mesh.iter().filter(|triangle| collapsed(triangle)).count();
+    if count == 0 {
+        return Ok(());
+    }
+    if let Some(max) = args.max_collapsed {
+        ensure!(
+            count as f64 <= max as f64 * mesh.len() as f64,
+            "{count} of the {} triangles of the {name} have repeated vertices, more than \
+             --max-collapsed allows",
+            mesh.len()
+        );
+    }
+    if args.drop_collapsed {
+        marks.retain(mesh, |triangle| !collapsed(triangle));
+        warn!("dropped {count} triangles of the {name} with repeated vertices");
+    } else {
+        warn!(
+            "{count} triangles of the {name} have repeated vertices, pass \
+             --drop-collapsed to skip them"
+        );
+    }
+    Ok(())
+}
+
+/// Mark the triangles in any of the groups called `names`, for --grow-on-group, of a mesh with
+/// `len` triangles.
+fn group_mask(groups: &[Group], names: &[String], len: usize) -> Result<Vec<bool>> {
+    let mut mask = vec![false; len];
+    for name in names {
+        let mut found = false;
+        for group in groups.iter().filter(|group| &group.name == name) {
+            mask[group.triangles.clone()].fill(true);
+            found = true;
+        }
+        if !found {
+            let mut known: Vec<&str> = groups.iter().map(|group| group.name.as_str()).collect();
+            known.sort();
+            known.dedup();
+            match known.is_empty() {
+                true => bail!("no group named {name}, as the input has no groups"),
+                false => bail!("no group named {name}, the input has {}", known.join(", ")),
+            }
+        }
+    }
+    Ok(mask)
+}
+
+/// Write each generation of `instances` to its own file for --split-by-generation, `pattern` with
+/// {} replaced by the generation. Generations with nothing in them aren't written.
+fn write_generations(
+    args: &Args,
+    pattern: &str,
+    instances: &[Instance],
+    depth: usize,
+    mut place: impl FnMut(&[Instance]) -> Mesh,
+    count: impl Fn(&[Instance]) -> usize,
+) -> Result<()> {
+    let header = args.header_text.as_deref();
+    let options = write_options(args);
+    for generation in 0..=depth {
+        let chosen: Vec<Instance> = instances
+            .iter()
+            .filter(|instance| instance.generation == generation)
+            .copied()
+            .collect();
+        if chosen.is_empty() {
+            continue;
+        }
+        let path = PathBuf::from(pattern.replace("{}", &generation.to_string()));
+        let triangles = chosen.chunks(STREAM_CHUNK).flat_map(&mut place);
+        let mut out = create_output(Some(&path), args)?;
+        match args.output_format {
+            Format::Stl if args.stl_ascii => stl::write_ascii(&mut out, header, triangles)?,
+            Format::Stl => stl::write_iter(&mut out, header, count(&chosen), triangles)?,
+            format => {
+                let mesh: Mesh = triangles.collect();
+                fractulate::format::write(&mut out, &mesh, format, &options)?
+            }
+        }
+        out.finish()?;
+        debug!("wrote generation {generation} to {}", path.display());
+    }
+    Ok(())
+}
+
+/// What's kept for each triangle of a mesh, following it as triangles are dropped, split or
+/// copied.
+#[derive(Default)]
+struct Marks {
+    /// Whether to grow on it, or for a child, whether it may be attached by it.
+    chosen: Option<Vec<bool>>,
+    /// Which way children on it turn, see [`GrowthParams::tangents`].
+    tangents: Option<Vec<Vector3<f32>>>,
 }
 
-/// Choose a random triangle, weighted by its area.
-// this could be sped up with some precomputation and a binary search but yolo
-fn select<R: Rng>(rng: &mut R, triangles: &[[Vector3<f32>; 3]]) -> [Vector3<f32>; 3] {
-    assert!(!triangles.is_empty());
+impl Marks {
+    /// Keep only the triangles of `mesh` that pass `keep`, along with their marks.
+    fn retain(&mut self, mesh: &mut Mesh, keep: impl Fn(&Triangle) -> bool) {
+        fn follow<T>(marks: &mut Option<Vec<T>>, mesh: &Mesh, keep: &impl Fn(&Triangle) -> bool) {
+            if let Some(marks) = marks {
+                let mut triangles = mesh.iter();
+                marks.retain(|_| triangles.next().is_some_and(keep));
+            }
+        }
+        follow(&mut self.chosen, mesh, &keep);
+        follow(&mut self.tangents, mesh, &keep);
+        mesh.triangles.retain(keep);
+    }
+
+    /// Mark each triangle's `count` replacements in its place as it was.
+    fn repeat_each(&mut self, count: usize) {
+        if let Some(chosen) = &mut self.chosen {
+            *chosen = chosen
+                .iter()
+                .flat_map(|&marked| std::iter::repeat_n(marked, count))
+                .collect();
+        }
+        if let Some(tangents) = &mut self.tangents {
+            *tangents = tangents
+                .iter()
+                .flat_map(|&tangent| std::iter::repeat_n(tangent, count))
+                .collect();
+        }
+    }
+
+    /// Mark copies of the whole mesh, one after another, until there are `len` triangles.
+    fn repeat(&mut self, len: usize) {
+        if let Some(chosen) = &mut self.chosen {
+            *chosen = chosen.repeat(len / chosen.len().max(1));
+        }
+        if let Some(tangents) = &mut self.tangents {
+            *tangents = tangents.repeat(len / tangents.len().max(1));
+        }
+    }
+}
 
-    let areas = triangles
+/// The direction each triangle of `mesh` is textured along, from the corners' texture
+/// coordinates `uvs` read with it, or zero where it has none that span it.
+fn uv_tangents(mesh: &Mesh, uvs: &[Option<[Vector2<f32>; 3]>]) -> Result<Vec<Vector3<f32>>> {
+    ensure!(
+        uvs.iter().any(Option::is_some),
+        "--follow-uvs needs an OBJ input with texture coordinates on its faces"
+    );
+    let tangents: Vec<Vector3<f32>> = mesh
         .iter()
-        .map(|triangle| {
-            let [t0, t1, t2] = triangle;
-            let a = t1 - t0;
-            let b = t2 - t0;
-            a.cross(&b).norm()
+        .enumerate()
+        .map(|(i, triangle)| {
+            uvs.get(i)
+                .copied()
+                .flatten()
+                .and_then(|uvs| fractulate::uv_tangent(triangle, &uvs))
+                .unwrap_or_default()
         })
-        .collect::<Vec<_>>();
-    let total_area = areas.iter().sum::<f32>();
-    let mut area = rng.gen_range(0.0..total_area);
-    for (i, &a) in areas.iter().enumerate() {
-        if area < a {
-            return triangles[i];
+        .collect();
+    let untextured = tangents.iter().filter(|t| **t == Vector3::zeros()).count();
+    if untextured > 0 {
+        warn!(
+            "{untextured} input triangles have no texture coordinates that span them, so \
+             children on them aren't turned to follow any"
+        );
+    }
+    Ok(tangents)
+}
+
+/// Copies of `mesh` in a grid `counts` copies along each axis, `spacing` apart, or else each
+/// axis's size apart.
+fn tile(mesh: &Mesh, counts: [u32; 3], spacing: Option<f32>) -> Result<Mesh> {
+    let copies = counts
+        .iter()
+        .try_fold(mesh.len(), |n, &c| n.checked_mul(c as usize));
+    let copies = copies.ok_or_else(|| anyhow!("--tile makes too many triangles"))?;
+    let spacing = match spacing {
+        Some(spacing) => Vector3::repeat(spacing),
+        None => fractulate::bounding_box(mesh).map_or_else(Vector3::zeros, |(min, max)| max - min),
+    };
+    let mut ret = Vec::with_capacity(copies);
+    let [nx, ny, nz] = counts;
+    for z in 0..nz {
+        for y in 0..ny {
+            for x in 0..nx {
+                let offset = Vector3::new(x, y, z).cast::<f32>().component_mul(&spacing);
+                let copy = fractulate::transform(mesh.clone(), Matrix4::new_translation(&offset));
+                ret.extend(copy.iter().copied());
+            }
         }
-        area -= a;
     }
+    Ok(ret.into())
+}
 
-    // probably floating point error, return the last triangle
-    triangles[triangles.len() - 1]
+/// A mesh as read from a file.
+struct Input {
+    mesh: Mesh,
+    /// The header if it's an STL with one, see [`stl::header`].
+    header: Option<String>,
+    /// Groups if it's an OBJ with any.
+    groups: Vec<Group>,
+    /// Texture coordinates of each triangle's corners if it's an OBJ, `None` for triangles
+    /// without. Empty for other formats.
+    uvs: Vec<Option<[Vector2<f32>; 3]>>,
 }
 
-fn transform(
-    triangles: Vec<[Vector3<f32>; 3]>,
-    transformation: Matrix4<f32>,
-) -> Vec<[Vector3<f32>; 3]> {
-    let mut triangles = triangles;
-    for triangle in triangles.iter_mut() {
-        for v in triangle.iter_mut() {
-            *v = transformation.transform_point(&(*v).into()).coords;
+/// Load and concatenate every mesh in `paths`, or the one on stdin if there are none. Each file's
+/// format is guessed separately unless `format` is given.
+/// The header is the first among them, and groups keep their names across files.
+fn load_inputs(paths: &[PathBuf], format: Option<Format>) -> Result<Input> {
+    if paths.is_empty() {
+        return load(None, format);
+    }
+    let mut mesh = Vec::new();
+    let mut header = None;
+    let mut groups = Vec::new();
+    let mut uvs = Vec::new();
+    for path in paths {
+        let loaded = load(Some(path), format)?;
+        let offset = mesh.len();
+        groups.extend(loaded.groups.into_iter().map(|group| Group {
+            triangles: group.triangles.start + offset..group.triangles.end + offset,
+            ..group
+        }));
+        // keep them lined up with the triangles, whichever files have them
+        if !loaded.uvs.is_empty() {
+            uvs.resize(offset, None);
+            uvs.extend(loaded.uvs);
         }
+        mesh.extend(loaded.mesh);
+        header = header.or(loaded.header);
     }
-    triangles
+    Ok(Input {
+        mesh: mesh.into(),
+        header,
+        groups,
+        uvs,
+    })
 }
 
-fn get_normal(face: &[Vector3<f32>; 3]) -> nalgebra::Vector3<f32> {
-    let a = face[1] - face[0];
-    let b = face[2] - face[0];
-    a.cross(&b).normalize()
+/// Bytes looked at to guess an input's format.
+const PEEK_LEN: u64 = 1 << 16;
+
+/// Read a mesh from `path`, or stdin if `None`.
+///
+/// Binary STL is parsed as it's read, since its triangles are a fixed size. Anything else is read
+/// into memory whole first.
+fn load(path: Option<&Path>, format: Option<Format>) -> Result<Input> {
+    let read_error = || match path {
+        Some(path) => format!("failed to read {}", path.display()),
+        None => "failed to read mesh from stdin".to_string(),
+    };
+    let input: Box<dyn Read> = match path {
+        Some(path) => Box::new(File::open(path).with_context(read_error)?),
+        None => Box::new(std::io::stdin()),
+    };
+    let (start, input) = peek(input).with_context(read_error)?;
+    ensure!(!start.is_empty(), "no input mesh provided");
+    let gzipped = start.starts_with(&[0x1f, 0x8b]);
+    let (start, mut input) = match gzipped {
+        true => peek(Box::new(MultiGzDecoder::new(input)))
+            .context("failed to decompress gzipped input")?,
+        false => (start, input),
+    };
+    let format = format.unwrap_or_else(|| Format::sniff(&start));
+    let parsed = match format {
+        // the same test stl_io uses to tell ascii from binary
+        Format::Stl if !start.starts_with(b"solid ") => stl::read_binary(input)
+            .map(|(mesh, header)| Input {
+                mesh,
+                header,
+                groups: Vec::new(),
+                uvs: Vec::new(),
+            })
+            .map_err(anyhow::Error::from),
+        _ => {
+            let mut buf = Vec::new();
+            match gzipped {
+                true => input
+                    .read_to_end(&mut buf)
+                    .context("failed to decompress gzipped input")?,
+                false => input.read_to_end(&mut buf).with_context(read_error)?,
+            };
+            match format {
+                Format::Obj => obj::read_all(&buf).map(|obj| Input {
+                    mesh: obj.mesh,
+                    header: None,
+                    groups: obj.groups,
+                    uvs: obj.uvs,
+                }),
+                _ => fractulate::format::read(&buf, format).map(|mesh| Input {
+                    mesh,
+                    header: stl::header(&buf).filter(|_| format == Format::Stl),
+                    groups: Vec::new(),
+                    uvs: Vec::new(),
+                }),
+            }
+        }
+    };
+    match path {
+        Some(path) => parsed.with_context(|| format!("failed to parse {}", path.display())),
+        None => parsed,
+    }
 }
 
-/// Create a transformation that would move a mesh so it sticks out from the triangle.
-pub fn place_on_triangle(triangle: [Vector3<f32>; 3]) -> Matrix4<f32> {
-    let [v0, v1, v2] = triangle;
+/// Read up to [`PEEK_LEN`] bytes from the start of `reader`, and a reader that starts over from
+/// the beginning.
+fn peek(reader: Box<dyn Read>) -> std::io::Result<(Vec<u8>, Box<dyn Read>)> {
+    let mut reader = BufReader::new(reader);
+    let mut start = Vec::new();
+    (&mut reader).take(PEEK_LEN).read_to_end(&mut start)?;
+    Ok((start.clone(), Box::new(Cursor::new(start).chain(reader))))
+}
 
-    let normal = get_normal(&triangle);
-    let x_axis = (v1 - v0).normalize();
-    let y_axis = normal.cross(&x_axis);
-    let rotation = Matrix4::new(
-        x_axis.x, y_axis.x, normal.x, 0.0, x_axis.y, y_axis.y, normal.y, 0.0, x_axis.z, y_axis.z,
-        normal.z, 0.0, 0.0, 0.0, 0.0, 1.0,
+/// The output settings the format writers need.
+fn write_options(args: &Args) -> WriteOptions {
+    WriteOptions {
+        stl_ascii: args.stl_ascii,
+        ply_ascii: args.ply_ascii,
+        stl_header: args.header_text.clone(),
+        weld_epsilon: args.weld_epsilon,
+        weld_angle: args.weld_angle.map(f32::to_radians),
+    }
+}
+
+/// Read `input` and write it to `output` for the convert command.
+fn convert(input: &Path, output: &Path, args: &mut Args, matches: &ArgMatches) -> Result<()> {
+    let gzipped = output
+        .extension()
+        .is_some_and(|extension| extension == "gz");
+    let named = match gzipped {
+        true => Path::new(output.file_stem().unwrap_or_default()),
+        false => output,
+    };
+    let format = match matches.value_source("output_format") {
+        Some(ValueSource::DefaultValue) | None => {
+            let extension = named
+                .extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or_default();
+            <Format as clap::ValueEnum>::from_str(extension, true).map_err(|_| {
+                anyhow!(
+                    "can't tell what format to write {} in from its extension, pass \
+                     --output-format",
+                    output.display()
+                )
+            })?
+        }
+        _ => args.output_format,
+    };
+    // anything else is for growing, so rather than quietly ignore it, say so
+    let command = Args::command();
+    let growing = command.get_arguments().find(|arg| {
+        !arg.is_global_set()
+            && matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine)
+    });
+    if let Some(arg) = growing {
+        let name = arg.get_long().unwrap_or(arg.get_id().as_str());
+        bail!("convert doesn't grow anything, so doesn't take --{name}");
+    }
+    let loaded = load(Some(input), args.format)?;
+    ensure_not_empty(loaded.mesh.len())?;
+    let options = WriteOptions {
+        stl_header: args.header_text.clone().or(loaded.header),
+        ..write_options(args)
+    };
+    args.gzip |= gzipped;
+    let mut out = create_output(Some(output), args)?;
+    fractulate::format::write(&mut out, &loaded.mesh, format, &options)?;
+    out.finish()?;
+    debug!(
+        "wrote {} triangles to {}",
+        loaded.mesh.len(),
+        output.display()
     );
+    Ok(())
+}
+
+/// An empty result means something upstream filtered out everything, so rather than write a
+/// valid but empty file, fail where a pipeline will notice.
+fn ensure_not_empty(triangles: usize) -> Result<()> {
+    if triangles == 0 {
+        return Err(FractulateError::EmptyResult).context("nothing was written");
+    }
+    Ok(())
+}
 
-    let center = (v0 + v1 + v2) / 3.0;
-    let translation = Matrix4::new_translation(&center);
+/// Write `mesh` as `format`, shaded by --bake-ao if asked.
+fn write_shaded(
+    out: &mut Output,
+    mesh: &[Triangle],
+    format: Format,
+    args: &Args,
+    options: &WriteOptions,
+) -> Result<()> {
+    let Some(samples) = args.bake_ao else {
+        return fractulate::format::write(out, mesh, format, options);
+    };
+    let welded = options.welded(mesh);
+    let colors: Vec<Rgb> = fractulate::ambient_occlusion(&welded, samples as usize)
+        .into_iter()
+        .map(|open| [(open * 255.0).round() as u8; 3])
+        .collect();
+    ply::write(out, &welded, Some(&colors), args.ply_ascii)
+}
 
-    translation * rotation
+/// Refuses to write binary to stdout when it's a terminal, which would only fill it with garbage,
+/// unless --force. Rust never translates line endings, so binary piped out on Windows arrives
+/// intact.
+fn create_output(path: Option<&Path>, args: &Args) -> Result<Output> {
+    let out: Box<dyn Write> = match path {
+        Some(path) => Box::new(
+            File::create(path).with_context(|| format!("failed to create {}", path.display()))?,
+        ),
+        None => {
+            let stdout = std::io::stdout();
+            let binary = match args.output_format {
+                _ if args.gzip => Some(("gzipped output", "")),
+                Format::Stl if !args.stl_ascii => Some(("binary STL", ", use --stl-ascii")),
+                Format::Ply if !args.ply_ascii => Some(("binary PLY", ", use --ply-ascii")),
+                _ => None,
+            };
+            if let Some((what, ascii)) = binary.filter(|_| stdout.is_terminal() && !args.force) {
+                bail!(
+                    "refusing to write {what} to a terminal; redirect to a file{ascii} or pass \
+                     --force"
+                );
+            }
+            Box::new(stdout)
+        }
+    };
+    Ok(if args.gzip {
+        Output::Gzip(GzEncoder::new(out, Compression::default()))
+    } else {
+        Output::Plain(out)
+    })
+}
+
+/// Where the fractal is written, maybe through gzip.
+enum Output {
+    Plain(Box<dyn Write>),
+    Gzip(GzEncoder<Box<dyn Write>>),
+}
+
+impl Output {
+    /// Flush everything out. Dropping a gzip stream finishes it too, but without reporting errors.
+    fn finish(self) -> Result<()> {
+        match self {
+            Output::Plain(mut out) => out.flush()?,
+            Output::Gzip(out) => out.finish()?.flush()?,
+        }
+        Ok(())
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Output::Plain(out) => out.write(buf),
+            Output::Gzip(out) => out.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Output::Plain(out) => out.flush(),
+            Output::Gzip(out) => out.flush(),
+        }
+    }
 }
@@ -0,0 +1,190 @@
+use nalgebra::{Vector2, Vector3};
+use std::collections::HashMap;
+
+use crate::IndexedMesh;
+
+/// Merge each patch of adjacent faces lying in one plane into a polygon, and triangulate that
+/// polygon again with as few triangles as its outline allows. Faces are in the same plane if
+/// their unit normals differ by at most `epsilon`, about the angle between them in radians, from
+/// the normal of the patch's first face. Undoes subdivision of flat faces, and shrinks output
+/// wherever the base mesh had large flat faces.
+///
+/// Vertices on a patch's outline are all kept, so neighboring patches still meet at the same
+/// vertices. Only vertices inside a patch go. Patches with holes, or whose outline touches
+/// itself, are left as they were, as are degenerate faces. Neighbors come from shared vertices,
+/// so weld the mesh first.
+pub fn merge_coplanar(mesh: &IndexedMesh, epsilon: f32) -> IndexedMesh {
+    let normals: Vec<Option<Vector3<f32>>> = mesh
+        .faces
+        .iter()
+        .map(|face| {
+            let [a, b, c] = face.map(|i| mesh.vertices[i]);
+            (b - a).cross(&(c - a)).try_normalize(f32::MIN_POSITIVE)
+        })
+        .collect();
+    let mut edge_faces: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+    for (i, &[a, b, c]) in mesh.faces.iter().enumerate() {
+        for (from, to) in [(a, b), (b, c), (c, a)] {
+            edge_faces
+                .entry((from.min(to), from.max(to)))
+                .or_default()
+                .push(i);
+        }
+    }
+
+    let mut merged = vec![false; mesh.faces.len()];
+    let mut faces = Vec::new();
+    for first in 0..mesh.faces.len() {
+        if merged[first] {
+            continue;
+        }
+        merged[first] = true;
+        let Some(normal) = normals[first] else {
+            faces.push(mesh.faces[first]);
+            continue;
+        };
+        // flood across edges shared by exactly two faces, so patches stay manifold
+        let mut patch = vec![first];
+        let mut next = 0;
+        while let Some(&face) = patch.get(next) {
+            next += 1;
+            let [a, b, c] = mesh.faces[face];
+            for (from, to) in [(a, b), (b, c), (c, a)] {
+                let &[x, y] = edge_faces[&(from.min(to), from.max(to))].as_slice() else {
+                    continue;
+                };
+                let neighbor = if x == face { y } else { x };
+                let coplanar = normals[neighbor].is_some_and(|n| (n - normal).norm() <= epsilon);
+                if !merged[neighbor] && coplanar {
+                    merged[neighbor] = true;
+                    patch.push(neighbor);
+                }
+            }
+        }
+        let retriangulated = (patch.len() > 1)
+            .then(|| outline(mesh, &patch))
+            .flatten()
+            .and_then(|outline| ear_clip(&mesh.vertices, &outline, &normal));
+        match retriangulated {
+            Some(triangles) => faces.extend(triangles),
+            None => faces.extend(patch.iter().map(|&i| mesh.faces[i])),
+        }
+    }
+
+    let mut ret = IndexedMesh {
+        vertices: mesh.vertices.clone(),
+        faces,
+    };
+    ret.remove_unused_vertices();
+    ret
+}
+
+/// The outline of a patch of faces as one loop of vertex indices, wound the same way as the
+/// faces. `None` if the patch has holes or its outline touches itself.
+fn outline(mesh: &IndexedMesh, patch: &[usize]) -> Option<Vec<usize>> {
+    let mut inner = HashMap::new();
+    for &face in patch {
+        let [a, b, c] = mesh.faces[face];
+        for edge in [(a, b), (b, c), (c, a)] {
+            *inner.entry(edge).or_insert(0) += 1;
+        }
+    }
+    // edges are on the outline if the face across them isn't in the patch
+    let mut next = HashMap::new();
+    for &(from, to) in inner.keys() {
+        if !inner.contains_key(&(to, from)) && next.insert(from, to).is_some() {
+            return None;
+        }
+    }
+    let (&start, _) = next.iter().min_by_key(|(&from, _)| from)?;
+    let mut ret = vec![start];
+    let mut at = next[&start];
+    while at != start {
+        if ret.len() >= next.len() {
+            return None;
+        }
+        ret.push(at);
+        at = *next.get(&at)?;
+    }
+    (ret.len() == next.len()).then_some(ret)
+}
+
+/// Triangulate a simple polygon lying in the plane with `normal`, wound anticlockwise seen from
+/// that side, by cutting off ears. `None` if it runs out of ears, as a polygon that crosses
+/// itself can.
+fn ear_clip(
+    vertices: &[Vector3<f32>],
+    polygon: &[usize],
+    normal: &Vector3<f32>,
+) -> Option<Vec<[usize; 3]>> {
+    let u = normal.cross(&Vector3::x()).try_normalize(f32::MIN_POSITIVE);
+    let u = u.unwrap_or_else(|| normal.cross(&Vector3::y()).normalize());
+    let v = normal.cross(&u);
+    let flat = |i: usize| Vector2::new(vertices[i].dot(&u), vertices[i].dot(&v));
+    let cross = |a: Vector2<f32>, b: Vector2<f32>, c: Vector2<f32>| (b - a).perp(&(c - a));
+
+    let mut remaining = polygon.to_vec();
+    let mut ret = Vec::with_capacity(polygon.len() - 2);
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let ear = (0..n).find(|&i| {
+            let [a, b, c] = [
+                remaining[(i + n - 1) % n],
+                remaining[i],
+                remaining[(i + 1) % n],
+            ];
+            let [pa, pb, pc] = [a, b, c].map(flat);
+            // straight corners make flat triangles, so only cut off ones that turn
+            if cross(pa, pb, pc) <= 0.0 {
+                return false;
+            }
+            // nothing else may be inside the ear, or on its edges
+            remaining.iter().all(|&other| {
+                let p = flat(other);
+                [a, b, c].contains(&other)
+                    || cross(pa, pb, p) < 0.0
+                    || cross(pb, pc, p) < 0.0
+                    || cross(pc, pa, p) < 0.0
+            })
+        })?;
+        ret.push([
+            remaining[(ear + n - 1) % n],
+            remaining[ear],
+            remaining[(ear + 1) % n],
+        ]);
+        remaining.remove(ear);
+    }
+    let [pa, pb, pc] = [remaining[0], remaining[1], remaining[2]].map(flat);
+    if cross(pa, pb, pc) <= 0.0 {
+        return None;
+    }
+    ret.push([remaining[0], remaining[1], remaining[2]]);
+    Some(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{weld, Mesh};
+
+    #[test]
+    fn merge_subdivided_cube() {
+        let cube = Mesh::new(crate::cube());
+        let fine = weld(&cube.subdivided().subdivided(), 0.0);
+        assert_eq!(fine.faces.len(), 192);
+        let merged = merge_coplanar(&fine, 1e-4);
+        // each side keeps the 16 vertices around its edge, which take 14 triangles
+        assert_eq!(merged.faces.len(), 6 * 14);
+        assert_eq!(merged.vertices.len(), 6 * 16 - 12 * 3 - 8 * 2);
+        assert_eq!(merged.watertightness(), Default::default());
+        let area = |mesh: &IndexedMesh| -> f32 {
+            mesh.triangles()
+                .map(|[a, b, c]| (b - a).cross(&(c - a)).norm() / 2.0)
+                .sum()
+        };
+        assert!((area(&merged) - 6.0).abs() < 1e-4);
+        // faces that aren't flat with each other stay apart
+        assert_eq!(merge_coplanar(&weld(&cube, 0.0), 1e-4).faces.len(), 12);
+        assert_eq!(merge_coplanar(&fine, -1.0), fine);
+    }
+}
@@ -0,0 +1,243 @@
+use nalgebra::{Matrix4, Vector3};
+use std::ops::{Deref, DerefMut};
+
+use crate::{area, Axis};
+
+/// Three vertices. Facing follows the right hand rule.
+pub type Triangle = [Vector3<f32>; 3];
+
+/// A triangle soup. Each triangle stores its own copy of its vertices.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Mesh {
+    pub triangles: Vec<Triangle>,
+}
+
+impl Mesh {
+    pub fn new(triangles: Vec<Triangle>) -> Self {
+        Self { triangles }
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    /// Total surface area.
+    pub fn area(&self) -> f32 {
+        self.triangles.iter().map(area).sum()
+    }
+
+    /// Minimum and maximum corners of the smallest axis aligned box containing the mesh, or `None`
+    /// if the mesh is empty.
+    pub fn bounding_box(&self) -> Option<(Vector3<f32>, Vector3<f32>)> {
+        bounding_box(&self.triangles)
+    }
+
+    /// Apply `transformation` to every vertex.
+    pub fn transform(&mut self, transformation: &Matrix4<f32>) {
+        for triangle in self.triangles.iter_mut() {
+            for v in triangle.iter_mut() {
+                *v = transformation.transform_point(&(*v).into()).coords;
+            }
+        }
+    }
+
+    /// Rotate each vertex about `axis` by `radians_per_unit` for each unit it is along it, so the
+    /// mesh winds around the axis like a screw. Positive angles turn anticlockwise looking back
+    /// down the axis.
+    ///
+    /// Unlike [`transform`](Self::transform) this bends the mesh, but only moves vertices, so
+    /// edges stay straight. Long edges cut corners off the twist, and can pass through each other
+    /// where the twist is tight.
+    pub fn twist(&mut self, axis: Axis, radians_per_unit: f32) {
+        let unit = axis.unit();
+        for v in self.triangles.iter_mut().flatten() {
+            let rotation = Matrix4::from_axis_angle(&unit, v.dot(&unit) * radians_per_unit);
+            *v = rotation.transform_point(&(*v).into()).coords;
+        }
+    }
+
+    /// A copy of the mesh with `transformation` applied to every vertex.
+    pub fn transformed(&self, transformation: &Matrix4<f32>) -> Mesh {
+        let mut ret = self.clone();
+        ret.transform(transformation);
+        ret
+    }
+
+    /// Reverse the winding of every triangle, turning the mesh inside out.
+    pub fn flip_normals(&mut self) {
+        for triangle in self.triangles.iter_mut() {
+            triangle.swap(1, 2);
+        }
+    }
+
+    /// Split every triangle into four at the midpoints of its edges. The surface is unchanged,
+    /// but has four times the triangles to grow on.
+    pub fn subdivided(&self) -> Mesh {
+        self.triangles
+            .iter()
+            .flat_map(|&[a, b, c]| {
+                let [ab, bc, ca] = [(a + b) / 2.0, (b + c) / 2.0, (c + a) / 2.0];
+                [[a, ab, ca], [ab, b, bc], [ca, bc, c], [ab, bc, ca]]
+            })
+            .collect()
+    }
+
+    /// Append the triangles of `other`.
+    pub fn extend(&mut self, other: &Mesh) {
+        self.triangles.extend_from_slice(&other.triangles);
+    }
+}
+
+/// Minimum and maximum corners of the smallest axis aligned box containing the triangles, or
+/// `None` if there are no triangles.
+pub fn bounding_box(triangles: &[Triangle]) -> Option<(Vector3<f32>, Vector3<f32>)> {
+    point_bounds(triangles.iter().flatten())
+}
+
+/// Minimum and maximum corners of the box containing `points`, or `None` if there are none.
+pub(crate) fn point_bounds<'a>(
+    points: impl IntoIterator<Item = &'a Vector3<f32>>,
+) -> Option<(Vector3<f32>, Vector3<f32>)> {
+    let mut points = points.into_iter();
+    let first = *points.next()?;
+    Some(points.fold((first, first), |(min, max), v| (min.inf(v), max.sup(v))))
+}
+
+/// A ball containing a mesh.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sphere {
+    pub center: Vector3<f32>,
+    pub radius: f32,
+}
+
+impl Sphere {
+    /// Whether the two balls overlap, touching not counting.
+    pub fn intersects(&self, other: &Sphere) -> bool {
+        (self.center - other.center).norm() < self.radius + other.radius
+    }
+}
+
+/// A sphere containing all the triangles, not necessarily the smallest. `None` if there are no
+/// triangles.
+///
+/// Found by Ritter's algorithm, which is usually within a few percent of the smallest, or taken
+/// around the center of the bounding box when that happens to be smaller.
+pub fn bounding_sphere(triangles: &[Triangle]) -> Option<Sphere> {
+    let (min, max) = bounding_box(triangles)?;
+    let around_box = sphere_around(triangles, (min + max) / 2.0);
+
+    // start from two points far apart, then grow to take in any points left outside
+    let points = || triangles.iter().flatten();
+    let farthest_from = |from: Vector3<f32>| {
+        *points()
+            .max_by(|a, b| {
+                (*a - from)
+                    .norm_squared()
+                    .total_cmp(&(*b - from).norm_squared())
+            })
+            .unwrap()
+    };
+    let a = farthest_from(triangles[0][0]);
+    let b = farthest_from(a);
+    let mut center = (a + b) / 2.0;
+    let mut radius = (b - a).norm() / 2.0;
+    for v in points() {
+        let distance = (v - center).norm();
+        if distance > radius {
+            // just big enough for both the old sphere and v
+            let grown = (radius + distance) / 2.0;
+            center += (v - center) * ((grown - radius) / distance);
+            radius = grown;
+        }
+    }
+    // rounding as the sphere grows can leave points just outside
+    let ritter = sphere_around(triangles, center);
+
+    Some(if ritter.radius < around_box.radius {
+        ritter
+    } else {
+        around_box
+    })
+}
+
+/// The smallest sphere centered on `center` containing all the triangles.
+fn sphere_around(triangles: &[Triangle], center: Vector3<f32>) -> Sphere {
+    let radius = triangles
+        .iter()
+        .flatten()
+        .map(|v| (v - center).norm())
+        .fold(0.0, f32::max);
+    Sphere { center, radius }
+}
+
+/// Scaling about the origin that makes a box from `min` to `max` fit inside `size`. Uniform, so
+/// the box keeps its shape and touches `size` along at least one axis, unless `stretch`, which
+/// scales each axis to match `size` exactly. Axes the box is flat along are left alone.
+pub fn fit_scaling(
+    (min, max): (Vector3<f32>, Vector3<f32>),
+    size: Vector3<f32>,
+    stretch: bool,
+) -> Matrix4<f32> {
+    let ratios = size.zip_map(&(max - min), |target, extent| {
+        (extent > 0.0).then(|| target / extent)
+    });
+    if stretch {
+        return Matrix4::new_nonuniform_scaling(&ratios.map(|r| r.unwrap_or(1.0)));
+    }
+    let scale = ratios.iter().flatten().copied().reduce(f32::min);
+    Matrix4::new_scaling(scale.unwrap_or(1.0))
+}
+
+/// A copy of `mesh` placed by each of `transforms`, in order, all in one mesh. For instancing a
+/// mesh at poses worked out some other way than by growing a fractal.
+pub fn apply_transforms(mesh: &Mesh, transforms: &[Matrix4<f32>]) -> Mesh {
+    let mut ret = Vec::with_capacity(mesh.len() * transforms.len());
+    for transformation in transforms {
+        ret.extend(mesh.transformed(transformation).triangles);
+    }
+    ret.into()
+}
+
+impl Deref for Mesh {
+    type Target = [Triangle];
+
+    fn deref(&self) -> &[Triangle] {
+        &self.triangles
+    }
+}
+
+impl DerefMut for Mesh {
+    fn deref_mut(&mut self) -> &mut [Triangle] {
+        &mut self.triangles
+    }
+}
+
+impl From<Vec<Triangle>> for Mesh {
+    fn from(triangles: Vec<Triangle>) -> Self {
+        Self::new(triangles)
+    }
+}
+
+impl FromIterator<Triangle> for Mesh {
+    fn from_iter<I: IntoIterator<Item = Triangle>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+impl IntoIterator for Mesh {
+    type Item = Triangle;
+    type IntoIter = std::vec::IntoIter<Triangle>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.triangles.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Mesh {
+    type Item = &'a Triangle;
+    type IntoIter = std::slice::Iter<'a, Triangle>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.triangles.iter()
+    }
+}
@@ -0,0 +1,74 @@
+use nalgebra::Vector3;
+use rayon::prelude::*;
+
+use crate::prune::{sphere_directions, Bvh};
+use crate::{bounding_box, IndexedMesh, Triangle};
+
+/// Share of the sky each vertex of `mesh` sees, from 0 where it's buried to 1 where none of the
+/// mesh is in the way, for shading crevices darker. Each vertex casts about `samples` rays,
+/// spread evenly over the half of the sphere its normal faces, and counts those that get out
+/// without hitting the mesh. Vertices with no normal cast twice as many, every way.
+///
+/// Normals come from the faces sharing each vertex, as in
+/// [`vertex_normals`](IndexedMesh::vertex_normals), so weld the mesh first.
+pub fn ambient_occlusion(mesh: &IndexedMesh, samples: usize) -> Vec<f32> {
+    let triangles: Vec<Triangle> = mesh.triangles().collect();
+    let Some((min, max)) = bounding_box(&triangles) else {
+        return vec![1.0; mesh.vertices.len()];
+    };
+    // rays start a little off the surface so they don't hit the faces around their vertex
+    let offset = (max - min).norm() * 1e-5;
+    let directions = sphere_directions(samples * 2);
+    let bvh = Bvh::new(&triangles);
+    let normals = mesh.vertex_normals();
+    mesh.vertices
+        .par_iter()
+        .zip(&normals)
+        .map(|(v, normal)| {
+            let facing = |direction: &&Vector3<f32>| {
+                *normal == Vector3::zeros() || direction.dot(normal) > 0.0
+            };
+            let origin = v + normal * offset;
+            let (open, cast) =
+                directions
+                    .iter()
+                    .filter(facing)
+                    .fold((0, 0), |(open, cast), direction| {
+                        let hit = bvh.hits(&triangles, origin, direction, usize::MAX);
+                        (open + usize::from(!hit), cast + 1)
+                    });
+            match cast {
+                0 => 1.0,
+                _ => open as f32 / cast as f32,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{weld, Mesh};
+
+    #[test]
+    fn floor_under_roof_is_dark() {
+        let v = Vector3::new;
+        let square = |size: f32, z: f32| {
+            let [a, b, c, d] = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+                .map(|(x, y)| v(x * size, y * size, z));
+            Mesh::new(vec![[a, b, c], [a, c, d]])
+        };
+        let floor = square(1.0, 0.0).subdivided();
+        let open = ambient_occlusion(&weld(&floor, 0.0), 64);
+        assert!(open.iter().all(|&open| open == 1.0));
+        // a roof just above, facing down onto the floor, hides all but the sky near the horizon
+        let mut covered = floor.clone();
+        let mut roof = square(2.0, 0.1);
+        roof.flip_normals();
+        covered.extend(&roof);
+        let welded = weld(&covered, 0.0);
+        let open = ambient_occlusion(&welded, 64);
+        let center = welded.vertices.iter().position(|v| *v == Vector3::zeros());
+        assert!(open[center.unwrap()] < 0.2);
+    }
+}
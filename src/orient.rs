@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+
+use crate::{weld, Triangle};
+
+/// Rewind triangles so neighbors agree on which side is out, then flip each connected piece
+/// whose enclosed volume comes out negative so closed pieces face outward. Triangles are
+/// neighbors when they share an edge, vertices must match exactly. Returns how many triangles
+/// were flipped.
+///
+/// Facing decides which way children grow, and normals are recomputed from winding on output, so
+/// this fixes both for inputs with inconsistent winding.
+pub fn orient(triangles: &mut [Triangle]) -> usize {
+    let welded = weld(triangles, 0.0);
+    let faces = &welded.faces;
+
+    // undirected edge -> faces using it, and whether each goes from the lower index to the higher
+    let mut edges = HashMap::<(usize, usize), Vec<(usize, bool)>>::new();
+    for (f, face) in faces.iter().enumerate() {
+        for (a, b) in directed_edges(face) {
+            if a != b {
+                edges
+                    .entry((a.min(b), a.max(b)))
+                    .or_default()
+                    .push((f, a < b));
+            }
+        }
+    }
+
+    let mut flip: Vec<Option<bool>> = vec![None; faces.len()];
+    let mut component = Vec::new();
+    for start in 0..faces.len() {
+        if flip[start].is_some() {
+            continue;
+        }
+        flip[start] = Some(false);
+        component.clear();
+        component.push(start);
+        let mut next = 0;
+        while let Some(&f) = component.get(next) {
+            next += 1;
+            let flip_f = flip[f].unwrap();
+            for (a, b) in directed_edges(&faces[f]) {
+                if a == b {
+                    continue;
+                }
+                let forward = (a < b) != flip_f;
+                for &(g, g_forward) in &edges[&(a.min(b), a.max(b))] {
+                    if flip[g].is_none() {
+                        // consistent neighbors run a shared edge in opposite directions
+                        flip[g] = Some(g_forward == forward);
+                        component.push(g);
+                    }
+                }
+            }
+        }
+
+        let volume: f32 = component
+            .iter()
+            .map(|&f| {
+                let [a, b, c] = faces[f].map(|i| welded.vertices[i]);
+                let volume = a.dot(&b.cross(&c));
+                if flip[f].unwrap() {
+                    -volume
+                } else {
+                    volume
+                }
+            })
+            .sum();
+        if volume < 0.0 {
+            for &f in &component {
+                flip[f] = flip[f].map(|flip| !flip);
+            }
+        }
+    }
+
+    let mut flipped = 0;
+    for (triangle, flip) in triangles.iter_mut().zip(flip) {
+        if flip.unwrap() {
+            triangle.swap(1, 2);
+            flipped += 1;
+        }
+    }
+    flipped
+}
+
+fn directed_edges(&[a, b, c]: &[usize; 3]) -> [(usize, usize); 3] {
+    [(a, b), (b, c), (c, a)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_normal;
+
+    /// Unit cube centered on the origin, two outward facing triangles per face.
+    fn cube() -> Vec<Triangle> {
+        crate::cube()
+            .iter()
+            .map(|face| face.map(|corner| corner.add_scalar(-0.5)))
+            .collect()
+    }
+
+    fn faces_outward(triangles: &[Triangle]) -> bool {
+        triangles
+            .iter()
+            .all(|t| get_normal(t).dot(&(t[0] + t[1] + t[2])) > 0.0)
+    }
+
+    #[test]
+    fn oriented_cube_is_untouched() {
+        let mut cube = cube();
+        assert!(faces_outward(&cube));
+        assert_eq!(orient(&mut cube), 0);
+        assert_eq!(cube, self::cube());
+    }
+
+    #[test]
+    fn orient_scrambled_cube() {
+        let mut cube = cube();
+        for i in [0, 3, 4, 9, 11] {
+            cube[i].swap(1, 2);
+        }
+        assert!(!faces_outward(&cube));
+        assert_eq!(orient(&mut cube), 5);
+        assert!(faces_outward(&cube));
+    }
+
+    #[test]
+    fn orient_inside_out_cube() {
+        let mut cube = cube();
+        for triangle in &mut cube {
+            triangle.swap(1, 2);
+        }
+        assert_eq!(orient(&mut cube), 12);
+        assert!(faces_outward(&cube));
+    }
+}
@@ -0,0 +1,180 @@
+use nalgebra::{Vector2, Vector3};
+use std::io::{self, Write};
+
+use crate::{try_normal, Triangle};
+
+/// Share of the image left empty around the mesh on each side.
+const MARGIN: f32 = 0.05;
+
+/// Brightness of surfaces facing away from the light, so they still stand out from the background.
+const AMBIENT: f32 = 0.15;
+
+/// A flat shaded picture of a mesh, seen from above and in front of its right side with z up,
+/// drawn a triangle at a time so the mesh can be streamed into it. Orthographic, so sizes on
+/// screen compare the same wherever they are.
+///
+/// Surfaces are shaded by how squarely they face a light behind the viewer's left shoulder,
+/// whichever way they're wound, so a fractal with inconsistent normals still looks solid. What
+/// isn't covered is transparent.
+pub struct Preview {
+    size: usize,
+    /// Screen directions in world space, right and up, and the direction towards the viewer.
+    right: Vector3<f32>,
+    up: Vector3<f32>,
+    toward: Vector3<f32>,
+    light: Vector3<f32>,
+    /// Screen position of the origin, and pixels per unit.
+    offset: Vector2<f32>,
+    scale: f32,
+    /// How near the viewer the surface drawn at each pixel is, row by row from the top.
+    depth: Vec<f32>,
+    shade: Vec<u8>,
+}
+
+impl Preview {
+    /// An empty picture `size` pixels square, framed to fit the box between `min` and `max`.
+    pub fn new(size: usize, (min, max): (Vector3<f32>, Vector3<f32>)) -> Self {
+        let toward = Vector3::new(1.0, -1.0, 1.0).normalize();
+        let right = Vector3::new(1.0, 1.0, 0.0).normalize();
+        let up = toward.cross(&right);
+        let light = (toward + up * 0.5 - right * 0.5).normalize();
+        let corners = (0..8).map(|i| {
+            let corner = Vector3::new(
+                if i & 1 == 0 { min.x } else { max.x },
+                if i & 2 == 0 { min.y } else { max.y },
+                if i & 4 == 0 { min.z } else { max.z },
+            );
+            Vector2::new(corner.dot(&right), corner.dot(&up))
+        });
+        let (low, high) = corners.fold(
+            (
+                Vector2::repeat(f32::INFINITY),
+                Vector2::repeat(f32::NEG_INFINITY),
+            ),
+            |(low, high), p| (low.inf(&p), high.sup(&p)),
+        );
+        let span = (high - low).max();
+        let scale = match span > 0.0 {
+            true => size as f32 * (1.0 - 2.0 * MARGIN) / span,
+            false => 1.0,
+        };
+        // image rows go down, screen up goes up
+        let center = (low + high) / 2.0;
+        let offset = Vector2::repeat(size as f32 / 2.0) - Vector2::new(center.x, -center.y) * scale;
+        Preview {
+            size,
+            right,
+            up,
+            toward,
+            light,
+            offset,
+            scale,
+            depth: vec![f32::NEG_INFINITY; size * size],
+            shade: vec![0; size * size],
+        }
+    }
+
+    /// Draw `triangle` over whatever it's in front of. Degenerate triangles have no side to shade,
+    /// and are skipped.
+    pub fn draw(&mut self, triangle: &Triangle) {
+        let Some(normal) = try_normal(triangle) else {
+            return;
+        };
+        let shade = self.shade_of(&normal);
+        let [a, b, c] = triangle.map(|v| {
+            let screen = Vector2::new(v.dot(&self.right), -v.dot(&self.up)) * self.scale;
+            (screen + self.offset, v.dot(&self.toward))
+        });
+        let edge =
+            |from: Vector2<f32>, to: Vector2<f32>, p: Vector2<f32>| (to - from).perp(&(p - from));
+        let area = edge(a.0, b.0, c.0);
+        if area.abs() < f32::MIN_POSITIVE {
+            return;
+        }
+        // only the pixels around the triangle need testing, and only those in the picture
+        let low = a.0.inf(&b.0).inf(&c.0);
+        let high = a.0.sup(&b.0).sup(&c.0);
+        let end = self.size as f32;
+        if self.size == 0 || high.x < 0.0 || high.y < 0.0 || low.x >= end || low.y >= end {
+            return;
+        }
+        let columns = low.x.max(0.0) as usize..=high.x.min(end - 1.0) as usize;
+        let rows = low.y.max(0.0) as usize..=high.y.min(end - 1.0) as usize;
+        for row in rows {
+            for column in columns.clone() {
+                // sample each pixel at its center
+                let p = Vector2::new(column as f32 + 0.5, row as f32 + 0.5);
+                let weights =
+                    [edge(b.0, c.0, p), edge(c.0, a.0, p), edge(a.0, b.0, p)].map(|w| w / area);
+                if weights.iter().any(|&w| w < 0.0) {
+                    continue;
+                }
+                let depth = weights[0] * a.1 + weights[1] * b.1 + weights[2] * c.1;
+                let i = row * self.size + column;
+                if depth > self.depth[i] {
+                    self.depth[i] = depth;
+                    self.shade[i] = shade;
+                }
+            }
+        }
+    }
+
+    /// Gray level of a surface with unit `normal`.
+    fn shade_of(&self, normal: &Vector3<f32>) -> u8 {
+        let brightness = AMBIENT + (1.0 - AMBIENT) * normal.dot(&self.light).abs();
+        (brightness * 255.0).round() as u8
+    }
+
+    pub fn extend<'a>(&mut self, triangles: impl IntoIterator<Item = &'a Triangle>) {
+        for triangle in triangles {
+            self.draw(triangle);
+        }
+    }
+
+    /// Whether anything was drawn at the pixel `column` across and `row` down from the top left.
+    pub fn covered(&self, column: usize, row: usize) -> bool {
+        self.depth[row * self.size + column] > f32::NEG_INFINITY
+    }
+
+    /// Write the picture as a grayscale PNG with transparency.
+    pub fn write_png(&self, writer: impl Write) -> io::Result<()> {
+        let size = u32::try_from(self.size).map_err(io::Error::other)?;
+        let mut encoder = png::Encoder::new(writer, size, size);
+        encoder.set_color(png::ColorType::GrayscaleAlpha);
+        encoder.set_depth(png::BitDepth::Eight);
+        let pixels: Vec<u8> = self
+            .shade
+            .iter()
+            .zip(&self.depth)
+            .flat_map(|(&shade, &depth)| [shade, if depth > f32::NEG_INFINITY { 255 } else { 0 }])
+            .collect();
+        let mut writer = encoder.write_header().map_err(io::Error::other)?;
+        writer.write_image_data(&pixels).map_err(io::Error::other)?;
+        writer.finish().map_err(io::Error::other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preview_tetrahedron() {
+        let v = Vector3::new;
+        let tetrahedron = crate::tetrahedron();
+        let mut preview = Preview::new(64, (Vector3::zeros(), v(1.0, 1.0, 1.0)));
+        preview.extend(tetrahedron.iter());
+        assert!(!preview.covered(0, 0));
+        // only the two faces towards the viewer show, the diagonal one and the one on y = 0
+        let front = [v(1.0, 1.0, 1.0).normalize(), -Vector3::y()].map(|n| preview.shade_of(&n));
+        let shown: Vec<u8> = (0..64 * 64)
+            .filter(|&i| preview.covered(i % 64, i / 64))
+            .map(|i| preview.shade[i])
+            .collect();
+        assert!(shown.iter().all(|shade| front.contains(shade)));
+        assert!(front.iter().all(|shade| shown.contains(shade)));
+        let mut png = Vec::new();
+        preview.write_png(&mut png).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+    }
+}
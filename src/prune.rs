@@ -0,0 +1,214 @@
+use nalgebra::Vector3;
+use rayon::prelude::*;
+
+use crate::{bounding_box, try_normal, Mesh, Triangle};
+
+/// Triangles per leaf of the [`Bvh`].
+const LEAF_SIZE: usize = 4;
+
+/// Remove triangles that can't be seen from outside the mesh, such as those buried inside where
+/// children overlap their parents. Lossy: a triangle is kept if any of `samples` rays cast from
+/// its centroid, spread evenly over the sphere of directions and facing the same side as the
+/// triangle, gets out without hitting another triangle. More samples keep fewer visible triangles
+/// by mistake and cost more time. Visible triangles seen only through gaps narrower than the
+/// rays are spread can still be removed.
+///
+/// Triangles are kept in the order they were in. Degenerate triangles have no side, so rays are
+/// cast every way from them.
+pub fn prune_hidden(mesh: &Mesh, samples: usize) -> Mesh {
+    let Some((min, max)) = bounding_box(mesh) else {
+        return mesh.clone();
+    };
+    // rays start a little off the surface so they don't hit what they start on
+    let offset = (max - min).norm() * 1e-5;
+    let directions = sphere_directions(samples);
+    let bvh = Bvh::new(mesh);
+    let visible: Vec<bool> = (0..mesh.len())
+        .into_par_iter()
+        .map(|i| {
+            let triangle = &mesh[i];
+            let [a, b, c] = triangle;
+            let centroid = (a + b + c) / 3.0;
+            let normal = try_normal(triangle);
+            directions.iter().any(|direction| {
+                let origin = match normal {
+                    Some(normal) if direction.dot(&normal) <= 0.0 => return false,
+                    Some(normal) => centroid + normal * offset,
+                    None => centroid,
+                };
+                !bvh.hits(mesh, origin, direction, i)
+            })
+        })
+        .collect();
+    mesh.iter()
+        .zip(visible)
+        .filter(|(_, visible)| *visible)
+        .map(|(triangle, _)| *triangle)
+        .collect()
+}
+
+/// `n` unit vectors spread evenly over the sphere, on a Fibonacci spiral.
+pub(crate) fn sphere_directions(n: usize) -> Vec<Vector3<f32>> {
+    let golden_angle = std::f32::consts::PI * (3.0 - 5.0f32.sqrt());
+    (0..n)
+        .map(|i| {
+            let z = 1.0 - (2.0 * i as f32 + 1.0) / n as f32;
+            let r = (1.0 - z * z).sqrt();
+            let theta = golden_angle * i as f32;
+            Vector3::new(r * theta.cos(), r * theta.sin(), z)
+        })
+        .collect()
+}
+
+/// Bounding volume hierarchy over a mesh's triangles, for finding what a ray hits without testing
+/// every triangle.
+pub(crate) struct Bvh {
+    nodes: Vec<Node>,
+    /// Triangle indices, grouped so each leaf's are contiguous.
+    order: Vec<usize>,
+}
+
+struct Node {
+    min: Vector3<f32>,
+    max: Vector3<f32>,
+    kind: NodeKind,
+}
+
+enum NodeKind {
+    /// Indices of the two children in [`Bvh::nodes`].
+    Branch(usize, usize),
+    /// Range of [`Bvh::order`] holding the leaf's triangles.
+    Leaf(usize, usize),
+}
+
+impl Bvh {
+    pub(crate) fn new(mesh: &[Triangle]) -> Self {
+        let mut ret = Bvh {
+            nodes: Vec::new(),
+            order: (0..mesh.len()).collect(),
+        };
+        ret.build(mesh, 0, mesh.len());
+        ret
+    }
+
+    /// Add a node for `order[start..end]` and everything under it, returning its index.
+    fn build(&mut self, mesh: &[Triangle], start: usize, end: usize) -> usize {
+        let (min, max) = self.order[start..end].iter().flat_map(|&i| &mesh[i]).fold(
+            (
+                Vector3::repeat(f32::INFINITY),
+                Vector3::repeat(f32::NEG_INFINITY),
+            ),
+            |(min, max), v| (min.inf(v), max.sup(v)),
+        );
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            min,
+            max,
+            kind: NodeKind::Leaf(start, end),
+        });
+        if end - start <= LEAF_SIZE {
+            return index;
+        }
+        // split at the median centroid along the longest axis
+        let axis = (max - min).imax();
+        let centroid = |i: usize| mesh[i].iter().map(|v| v[axis]).sum::<f32>();
+        let middle = (start + end) / 2;
+        self.order[start..end]
+            .select_nth_unstable_by(middle - start, |&a, &b| centroid(a).total_cmp(&centroid(b)));
+        let left = self.build(mesh, start, middle);
+        let right = self.build(mesh, middle, end);
+        self.nodes[index].kind = NodeKind::Branch(left, right);
+        index
+    }
+
+    /// Whether the ray from `origin` along `direction` hits any triangle but the `skip`th.
+    pub(crate) fn hits(
+        &self,
+        mesh: &[Triangle],
+        origin: Vector3<f32>,
+        direction: &Vector3<f32>,
+        skip: usize,
+    ) -> bool {
+        let inverse = direction.map(|d| 1.0 / d);
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            if !ray_hits_box(&origin, &inverse, &node.min, &node.max) {
+                continue;
+            }
+            match node.kind {
+                NodeKind::Branch(left, right) => stack.extend([left, right]),
+                NodeKind::Leaf(start, end) => {
+                    let hit = self.order[start..end]
+                        .iter()
+                        .any(|&i| i != skip && ray_hits_triangle(&origin, direction, &mesh[i]));
+                    if hit {
+                        return true;
+                    }
+                }
+            }
+        }
+        false
+    }
+}
+
+/// Slab test of a ray, given by its origin and the reciprocal of its direction, against a box.
+fn ray_hits_box(
+    origin: &Vector3<f32>,
+    inverse: &Vector3<f32>,
+    min: &Vector3<f32>,
+    max: &Vector3<f32>,
+) -> bool {
+    let (mut near, mut far) = (0.0f32, f32::INFINITY);
+    for axis in 0..3 {
+        let a = (min[axis] - origin[axis]) * inverse[axis];
+        let b = (max[axis] - origin[axis]) * inverse[axis];
+        // NaN from a ray along the face of a flat box compares false and leaves near and far alone
+        near = near.max(a.min(b));
+        far = far.min(a.max(b));
+    }
+    near <= far
+}
+
+/// Möller–Trumbore intersection of a ray with a triangle, from either side.
+fn ray_hits_triangle(origin: &Vector3<f32>, direction: &Vector3<f32>, triangle: &Triangle) -> bool {
+    let [a, b, c] = triangle;
+    let (ab, ac) = (b - a, c - a);
+    let p = direction.cross(&ac);
+    let determinant = ab.dot(&p);
+    if determinant.abs() < f32::MIN_POSITIVE {
+        return false;
+    }
+    let to_origin = origin - a;
+    let u = to_origin.dot(&p) / determinant;
+    if !(0.0..=1.0).contains(&u) {
+        return false;
+    }
+    let q = to_origin.cross(&ab);
+    let v = direction.dot(&q) / determinant;
+    if v < 0.0 || u + v > 1.0 {
+        return false;
+    }
+    ac.dot(&q) / determinant > 0.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prune_tetrahedron_inside_tetrahedron() {
+        let tetrahedron = |scale: f32| -> Vec<Triangle> {
+            let offset = Vector3::repeat(1.0 - scale) * 0.1;
+            let tetrahedron = crate::tetrahedron();
+            tetrahedron
+                .iter()
+                .map(|triangle| triangle.map(|p| p * scale + offset))
+                .collect()
+        };
+        let mut triangles = tetrahedron(1.0);
+        triangles.extend(tetrahedron(0.5));
+        let pruned = prune_hidden(&Mesh::new(triangles), 64);
+        assert_eq!(pruned.to_vec(), tetrahedron(1.0));
+    }
+}
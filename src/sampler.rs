@@ -0,0 +1,211 @@
+use crate::Real;
+use rand::Rng;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Most weights a sampler scans rather than bisects, since scanning so few is quicker. Measured
+/// with the `sample` benchmark, where the two cross over at about this many.
+const LINEAR_MAX: usize = 32;
+
+/// How near the end of the weights, as a share of their total, a draw lands to count as having
+/// got there by rounding, unless [`WeightedSampler::with_tolerance`] says otherwise. A few units in
+/// the last place of [`Real`], so finer with the `f64` feature.
+pub const DEFAULT_SAMPLE_TOLERANCE: Real = 4.0 * Real::EPSILON;
+
+/// How a [`WeightedSampler`] finds the index a draw lands on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Search {
+    /// Count the cumulative weights up to the draw, O(n) but without branches to mispredict, so
+    /// quicker for a short list.
+    Linear,
+    /// Bisect the cumulative weights, O(log n).
+    Binary,
+}
+
+impl Search {
+    /// The search a sampler over `len` weights uses.
+    pub fn for_len(len: usize) -> Self {
+        match len <= LINEAR_MAX {
+            true => Search::Linear,
+            false => Search::Binary,
+        }
+    }
+}
+
+impl fmt::Display for Search {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Search::Linear => "linear",
+            Search::Binary => "binary",
+        })
+    }
+}
+
+/// Draws indices with probability proportional to their weight.
+///
+/// Building the sampler is O(n), after which each draw searches the cumulative weights, in
+/// O(log n) for all but a few weights. Either search picks the same index.
+///
+/// The weights are summed in [`Real`], so with the `f64` feature the running total rounds as
+/// finely as `f64` does, see [`WeightedSampler::fallbacks`].
+#[derive(Debug)]
+pub struct WeightedSampler {
+    cumulative: Vec<Real>,
+    /// Draws from here on count as falling back, see [`WeightedSampler::with_tolerance`].
+    fallback_from: Real,
+    /// Draws so far that fell back.
+    fallbacks: AtomicUsize,
+}
+
+impl Clone for WeightedSampler {
+    /// The clone starts with no fallbacks counted.
+    fn clone(&self) -> Self {
+        Self {
+            cumulative: self.cumulative.clone(),
+            fallback_from: self.fallback_from,
+            fallbacks: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl WeightedSampler {
+    /// `None` if there are no weights, any is negative or not finite, or they don't sum to a
+    /// positive number. Searching relies on the running totals never going down.
+    pub fn new(weights: impl IntoIterator<Item = f32>) -> Option<Self> {
+        let cumulative: Option<Vec<Real>> = weights
+            .into_iter()
+            .scan(0.0, |total, w| {
+                *total += Real::from(w);
+                Some((w.is_finite() && w >= 0.0).then_some(*total))
+            })
+            .collect();
+        let cumulative = cumulative?;
+        let total = *cumulative.last()?;
+        let sampler = (total > 0.0 && total.is_finite()).then_some(Self {
+            cumulative,
+            fallback_from: total,
+            fallbacks: AtomicUsize::new(0),
+        })?;
+        Some(sampler.with_tolerance(DEFAULT_SAMPLE_TOLERANCE))
+    }
+
+    /// Take draws landing within `tolerance` times the total of the end of the weights to have
+    /// got there by rounding, giving them to the last index with any weight and counting them in
+    /// [`WeightedSampler::fallbacks`]. Rounding in the running total could otherwise hand them to
+    /// a weight too small to tell apart from it. 0 only falls back for draws past the end, which
+    /// [`WeightedSampler::sample`] never makes.
+    pub fn with_tolerance(mut self, tolerance: Real) -> Self {
+        self.fallback_from = self.total() - self.total() * tolerance;
+        self
+    }
+
+    /// Sum of all weights.
+    pub fn total(&self) -> Real {
+        self.cumulative[self.cumulative.len() - 1]
+    }
+
+    /// Number of weights, including zero ones.
+    pub fn len(&self) -> usize {
+        self.cumulative.len()
+    }
+
+    /// Never true, since a sampler needs some positive weight.
+    pub fn is_empty(&self) -> bool {
+        self.cumulative.is_empty()
+    }
+
+    /// Weight of index `i`.
+    pub fn weight(&self, i: usize) -> f32 {
+        let weight = match i {
+            0 => self.cumulative[0],
+            _ => self.cumulative[i] - self.cumulative[i - 1],
+        };
+        // a no-op unless the weights are summed in f64
+        #[allow(clippy::unnecessary_cast)]
+        let weight = weight as f32;
+        weight
+    }
+
+    /// How draws are searched for, by how many weights there are.
+    pub fn search(&self) -> Search {
+        Search::for_len(self.len())
+    }
+
+    /// How many draws by this sampler so far landed within its tolerance of the end of the
+    /// weights, see [`WeightedSampler::with_tolerance`], and so took the last index with any
+    /// weight. About the tolerance's share of draws land there by chance. Many more means the
+    /// last weights are too small next to the total for [`Real`] to tell apart, and the `f64`
+    /// feature may help.
+    pub fn fallbacks(&self) -> usize {
+        self.fallbacks.load(Ordering::Relaxed)
+    }
+
+    /// Pick an index.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        self.index_at(rng.gen_range(0.0..self.total()))
+    }
+
+    /// Index whose share of the weights, laid end to end from 0 to the total, covers `target`.
+    fn index_at(&self, target: Real) -> usize {
+        if target >= self.fallback_from {
+            self.fallbacks.fetch_add(1, Ordering::Relaxed);
+            return self.cumulative.partition_point(|&c| c < self.total());
+        }
+        match self.search() {
+            Search::Linear => self.cumulative.iter().filter(|&&c| c <= target).count(),
+            Search::Binary => self.cumulative.partition_point(|&c| c <= target),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn falls_back_to_last_weighted() {
+        assert!(WeightedSampler::new([]).is_none());
+        assert!(WeightedSampler::new([0.0, 0.0]).is_none());
+        // a weight below zero would let the running totals go down, which searching can't handle
+        assert!(WeightedSampler::new([-1.0, 2.0]).is_none());
+        assert!(WeightedSampler::new([f32::NAN, 1.0]).is_none());
+        assert!(WeightedSampler::new([f32::INFINITY, 1.0]).is_none());
+        let sampler = WeightedSampler::new([1.0, 0.0, 2.0, 0.0, 0.0]).unwrap();
+        assert_eq!(sampler.index_at(0.5), 0);
+        assert_eq!(sampler.index_at(1.0), 2);
+        // past the end, as rounding might leave a target, picks the last index with any weight
+        // rather than one of the zero weights after it
+        assert_eq!(sampler.fallbacks(), 0);
+        assert_eq!(sampler.index_at(sampler.total()), 2);
+        assert_eq!(sampler.index_at(Real::INFINITY), 2);
+        assert_eq!(sampler.fallbacks(), 2);
+        assert_eq!(sampler.clone().fallbacks(), 0);
+        // as does a draw within the tolerance of the end, but not one short of it
+        let total = sampler.total();
+        assert_eq!(
+            sampler.index_at(total - total * DEFAULT_SAMPLE_TOLERANCE / 2.0),
+            2
+        );
+        assert_eq!(sampler.fallbacks(), 3);
+        assert_eq!(
+            sampler.index_at(total - total * DEFAULT_SAMPLE_TOLERANCE * 2.0),
+            2
+        );
+        assert_eq!(sampler.fallbacks(), 3);
+        // with the whole range in the tolerance, every draw falls back
+        let sampler = sampler.clone().with_tolerance(1.0);
+        let mut rng = rand::rngs::mock::StepRng::new(0, 1 << 60);
+        for _ in 0..10 {
+            assert_eq!(sampler.sample(&mut rng), 2);
+        }
+        assert_eq!(sampler.fallbacks(), 10);
+        assert_eq!(sampler.search(), Search::Linear);
+        // bisecting lands on the same indices
+        let weights = (0..LINEAR_MAX + 2).map(|i| [1.0, 0.0][i % 2]);
+        let sampler = WeightedSampler::new(weights).unwrap();
+        assert_eq!(sampler.search(), Search::Binary);
+        assert_eq!(sampler.index_at(0.5), 0);
+        assert_eq!(sampler.index_at(1.0), 2);
+        assert_eq!(sampler.index_at(sampler.total()), LINEAR_MAX);
+    }
+}
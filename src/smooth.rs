@@ -0,0 +1,105 @@
+use nalgebra::Vector3;
+use rayon::prelude::*;
+
+use crate::IndexedMesh;
+
+/// Laplacian smoothing. Each round moves every vertex `lambda` of the way toward the average of
+/// its neighbors, so 1.0 moves vertices all the way and small values smooth gently.
+///
+/// Vertices on the boundary of an open surface stay put, otherwise open edges would shrink away
+/// from where they're meant to be. Neighbors come from shared faces, so weld the mesh first or
+/// every triangle is its own island and nothing moves.
+pub fn smooth(mesh: &mut IndexedMesh, iterations: usize, lambda: f32) {
+    let neighbors = neighbors(mesh);
+    let pinned = boundary(mesh);
+    for _ in 0..iterations {
+        let old = &mesh.vertices;
+        let new: Vec<Vector3<f32>> = (0..old.len())
+            .into_par_iter()
+            .map(|v| {
+                if pinned[v] || neighbors[v].is_empty() {
+                    return old[v];
+                }
+                let sum: Vector3<f32> = neighbors[v].iter().map(|&n| old[n]).sum();
+                let average = sum / neighbors[v].len() as f32;
+                old[v] + (average - old[v]) * lambda
+            })
+            .collect();
+        mesh.vertices = new;
+    }
+}
+
+/// The vertices each vertex shares an edge with.
+fn neighbors(mesh: &IndexedMesh) -> Vec<Vec<usize>> {
+    let mut ret = vec![Vec::new(); mesh.vertices.len()];
+    for &[a, b, c] in &mesh.faces {
+        for (from, to) in [(a, b), (b, c), (c, a), (b, a), (c, b), (a, c)] {
+            if from != to {
+                ret[from].push(to);
+            }
+        }
+    }
+    for neighbors in &mut ret {
+        neighbors.sort_unstable();
+        neighbors.dedup();
+    }
+    ret
+}
+
+/// Whether each vertex is on an edge used by only one face.
+fn boundary(mesh: &IndexedMesh) -> Vec<bool> {
+    let mut ret = vec![false; mesh.vertices.len()];
+    for ((a, b), faces) in mesh.edge_faces() {
+        if faces == 1 {
+            ret[a] = true;
+            ret[b] = true;
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::weld;
+
+    #[test]
+    fn boundary_is_pinned() {
+        let v = Vector3::new;
+        // a square of two triangles with a raised center vertex, only the center can move
+        let mut mesh = IndexedMesh {
+            vertices: vec![
+                v(0.0, 0.0, 0.0),
+                v(1.0, 0.0, 0.0),
+                v(1.0, 1.0, 0.0),
+                v(0.0, 1.0, 0.0),
+                v(0.5, 0.5, 1.0),
+            ],
+            faces: vec![[0, 1, 4], [1, 2, 4], [2, 3, 4], [3, 0, 4]],
+        };
+        let corners = mesh.vertices[..4].to_vec();
+        smooth(&mut mesh, 10, 1.0);
+        assert_eq!(mesh.vertices[..4], corners);
+        assert!((mesh.vertices[4] - v(0.5, 0.5, 0.0)).norm() < 1e-6);
+    }
+
+    #[test]
+    fn closed_surfaces_shrink() {
+        let v = Vector3::new;
+        let [o, x, y, z] = [
+            v(0.0, 0.0, 0.0),
+            v(1.0, 0.0, 0.0),
+            v(0.0, 1.0, 0.0),
+            v(0.0, 0.0, 1.0),
+        ];
+        let mut mesh = weld(&crate::tetrahedron(), 0.0);
+        smooth(&mut mesh, 1, 0.5);
+        // every vertex of a tetrahedron neighbors the other three
+        let sum = o + x + y + z;
+        // welding numbers vertices in order of first appearance
+        for (before, after) in [o, y, x, z].iter().zip(&mesh.vertices) {
+            let average = (sum - before) / 3.0;
+            assert!((before + (average - before) * 0.5 - after).norm() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use nalgebra::Vector3;
+use std::fmt;
+
+use crate::{area, Sphere, Triangle};
+
+/// Summary of a mesh. Built up a triangle at a time so it can be gathered while the mesh is
+/// streamed out.
+#[derive(Clone, Debug, Default)]
+pub struct Stats {
+    pub triangles: usize,
+    /// Total surface area. Accumulated in f64 since huge fractals have millions of tiny triangles.
+    pub area: f64,
+    /// Minimum and maximum corners of the bounding box, `None` when there are no triangles.
+    pub bounds: Option<(Vector3<f32>, Vector3<f32>)>,
+    /// Sum of each triangle's centroid weighted by its area.
+    pub moment: Vector3<f64>,
+    /// Signed volume enclosed, by the divergence theorem. Only meaningful for a closed mesh with
+    /// its normals facing out, and overlapping parts are counted more than once.
+    pub volume: f64,
+    /// Whether the mesh was found to be closed, `None` if nobody checked. Decides how much to
+    /// trust `volume`.
+    pub watertight: Option<bool>,
+    /// Box counting dimension, from a [`BoxCounter`](crate::BoxCounter). `None` if not measured.
+    pub dimension: Option<f64>,
+    /// A sphere containing the mesh, such as from [`bounding_sphere`](crate::bounding_sphere).
+    /// Needs every triangle at once, so `None` until someone works it out.
+    pub sphere: Option<Sphere>,
+}
+
+impl Stats {
+    pub fn of(triangles: &[Triangle]) -> Self {
+        let mut ret = Self::default();
+        ret.extend(triangles);
+        ret
+    }
+
+    pub fn add(&mut self, triangle: &Triangle) {
+        self.triangles += 1;
+        let area = area(triangle) as f64;
+        self.area += area;
+        let [a, b, c] = triangle.map(|v| v.cast::<f64>());
+        self.moment += (a + b + c) * (area / 3.0);
+        // each triangle spans a tetrahedron with the origin, which the closed surface's volume is
+        // the signed sum of
+        self.volume += a.dot(&b.cross(&c)) / 6.0;
+        for v in triangle {
+            self.bounds = Some(match self.bounds {
+                Some((min, max)) => (min.inf(v), max.sup(v)),
+                None => (*v, *v),
+            });
+        }
+    }
+
+    pub fn extend<'a>(&mut self, triangles: impl IntoIterator<Item = &'a Triangle>) {
+        for triangle in triangles {
+            self.add(triangle);
+        }
+    }
+
+    /// Center of mass of the surface, `None` when it has no area.
+    pub fn centroid(&self) -> Option<Vector3<f32>> {
+        (self.area > 0.0).then(|| (self.moment / self.area).cast())
+    }
+
+    /// Dimensions of the bounding box.
+    pub fn size(&self) -> Option<Vector3<f32>> {
+        self.bounds.map(|(min, max)| max - min)
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "  triangles: {}", self.triangles)?;
+        writeln!(f, "  surface area: {}", self.area)?;
+        let caveat = match self.watertight {
+            Some(true) => "",
+            Some(false) => " (unreliable, the mesh isn't watertight)",
+            None => " (assuming the mesh is watertight)",
+        };
+        writeln!(f, "  volume: {}{caveat}", self.volume)?;
+        if let Some(dimension) = self.dimension {
+            writeln!(f, "  box counting dimension: ~{dimension:.2}")?;
+        }
+        if let Some(sphere) = self.sphere {
+            writeln!(
+                f,
+                "  bounding sphere: center {}, radius {}",
+                fmt_vector(&sphere.center),
+                sphere.radius
+            )?;
+        }
+        match (self.bounds, self.size()) {
+            (Some((min, max)), Some(size)) => {
+                writeln!(
+                    f,
+                    "  bounding box: {} to {}",
+                    fmt_vector(&min),
+                    fmt_vector(&max)
+                )?;
+                write!(f, "  size: {}", fmt_vector(&size))
+            }
+            _ => write!(f, "  bounding box: empty"),
+        }
+    }
+}
+
+/// How a mesh's triangles spread over sizes, in bins of area growing by the same factor each, from
+/// the smallest triangle with any area to the largest. Shows where children will cluster, since
+/// by default they're placed in proportion to area.
+#[derive(Clone, Debug, Default)]
+pub struct AreaHistogram {
+    /// Smallest areas first.
+    pub bins: Vec<AreaBin>,
+    /// Triangles with no area, which aren't in any bin.
+    pub degenerate: usize,
+}
+
+/// One bin of an [`AreaHistogram`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AreaBin {
+    /// Range of areas in the bin.
+    pub min: f64,
+    pub max: f64,
+    pub triangles: usize,
+    /// Fraction of the mesh's total area in the bin.
+    pub share: f64,
+}
+
+impl AreaHistogram {
+    /// Sort `triangles` into `bins` bins.
+    pub fn of(triangles: &[Triangle], bins: usize) -> Self {
+        let areas: Vec<f64> = triangles.iter().map(|t| area(t) as f64).collect();
+        let positive = || areas.iter().copied().filter(|&a| a > 0.0);
+        let degenerate = areas.len() - positive().count();
+        let (Some(min), Some(max)) = (positive().reduce(f64::min), positive().reduce(f64::max))
+        else {
+            return AreaHistogram {
+                bins: Vec::new(),
+                degenerate,
+            };
+        };
+        let bins = bins.max(1);
+        let total: f64 = positive().sum();
+        let step = (max / min).ln() / bins as f64;
+        let bound = |i: usize| min * (step * i as f64).exp();
+        let mut ret: Vec<AreaBin> = (0..bins)
+            .map(|i| AreaBin {
+                min: bound(i),
+                max: bound(i + 1),
+                ..Default::default()
+            })
+            .collect();
+        for a in positive() {
+            // the largest lands on the upper edge of the last bin rather than past it
+            let i = if step > 0.0 {
+                (((a / min).ln() / step) as usize).min(bins - 1)
+            } else {
+                0
+            };
+            ret[i].triangles += 1;
+            ret[i].share += a / total;
+        }
+        if let Some(last) = ret.last_mut() {
+            last.max = max;
+        }
+        AreaHistogram {
+            bins: ret,
+            degenerate,
+        }
+    }
+}
+
+impl fmt::Display for AreaHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        /// Characters in the bar of a bin holding all the area.
+        const WIDTH: f64 = 40.0;
+        writeln!(f, "  {:<18} {:>9} {:>7}", "area", "triangles", "of area")?;
+        for bin in &self.bins {
+            let bar = "#".repeat((bin.share * WIDTH).round() as usize);
+            let line = format!(
+                "  {:.2e} to {:.2e} {:>9} {:>6.0}% {bar}",
+                bin.min,
+                bin.max,
+                bin.triangles,
+                bin.share * 100.0
+            );
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        write!(f, "  no area: {}", self.degenerate)
+    }
+}
+
+fn fmt_vector(v: &Vector3<f32>) -> String {
+    format!("({}, {}, {})", v.x, v.y, v.z)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn area_histogram_bins_by_magnitude() {
+        let v = Vector3::new;
+        let triangle = |size: f32| [v(0.0, 0.0, 0.0), v(size, 0.0, 0.0), v(0.0, size, 0.0)];
+        let triangles = [triangle(1.0), triangle(1.0), triangle(10.0), triangle(0.0)];
+        let histogram = AreaHistogram::of(&triangles, 4);
+        assert_eq!(histogram.degenerate, 1);
+        let counts: Vec<usize> = histogram.bins.iter().map(|bin| bin.triangles).collect();
+        assert_eq!(counts, [2, 0, 0, 1]);
+        assert!((histogram.bins[3].share - 50.0 / 51.0).abs() < 1e-9);
+        assert_eq!(histogram.bins[0].min, 0.5);
+        assert_eq!(histogram.bins[3].max, 50.0);
+    }
+}
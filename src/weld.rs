@@ -0,0 +1,282 @@
+use nalgebra::{Matrix4, Vector3};
+use std::collections::{HashMap, HashSet};
+
+use crate::Triangle;
+
+/// A mesh where triangles share vertices by index.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IndexedMesh {
+    pub vertices: Vec<Vector3<f32>>,
+    pub faces: Vec<[usize; 3]>,
+}
+
+impl IndexedMesh {
+    /// Expand back into a triangle soup.
+    pub fn triangles(&self) -> impl Iterator<Item = Triangle> + '_ {
+        self.faces.iter().map(|face| face.map(|i| self.vertices[i]))
+    }
+
+    /// Apply `transformation` to every vertex. Each vertex is only moved once, however many faces
+    /// share it.
+    pub fn transform(&mut self, transformation: &Matrix4<f32>) {
+        for v in &mut self.vertices {
+            *v = transformation.transform_point(&(*v).into()).coords;
+        }
+    }
+
+    /// How many faces use each edge, keyed by the edge's vertices lowest first. Edges of faces
+    /// whose vertices got merged together are left out.
+    pub fn edge_faces(&self) -> HashMap<(usize, usize), usize> {
+        let mut ret = HashMap::new();
+        for &[a, b, c] in &self.faces {
+            for (from, to) in [(a, b), (b, c), (c, a)] {
+                if from != to {
+                    *ret.entry((from.min(to), from.max(to))).or_default() += 1;
+                }
+            }
+        }
+        ret
+    }
+
+    /// Count the edges that stop the mesh being a closed surface.
+    pub fn watertightness(&self) -> Watertightness {
+        let mut ret = Watertightness::default();
+        for faces in self.edge_faces().into_values() {
+            match faces {
+                1 => ret.boundary_edges += 1,
+                2 => {}
+                _ => ret.non_manifold_edges += 1,
+            }
+        }
+        ret
+    }
+
+    /// Unit normal at each vertex, the average of the normals of the faces using it weighted by
+    /// their area. Zero for vertices only used by degenerate faces.
+    pub fn vertex_normals(&self) -> Vec<Vector3<f32>> {
+        let mut ret = vec![Vector3::zeros(); self.vertices.len()];
+        for face in &self.faces {
+            let [a, b, c] = face.map(|i| self.vertices[i]);
+            // the cross product's length is twice the area, so summing them weights by area
+            let weighted = (b - a).cross(&(c - a));
+            for &i in face {
+                ret[i] += weighted;
+            }
+        }
+        for normal in &mut ret {
+            *normal = normal.try_normalize(f32::MIN_POSITIVE).unwrap_or_default();
+        }
+        ret
+    }
+
+    /// Remove faces using the same three vertices as an earlier face, whichever way round either
+    /// winds, and return how many were removed. Faces only match if they share vertices, so weld
+    /// coincident ones first.
+    pub fn dedup_faces(&mut self) -> usize {
+        let before = self.faces.len();
+        let mut seen = HashSet::new();
+        self.faces.retain(|face| {
+            let mut key = *face;
+            key.sort_unstable();
+            seen.insert(key)
+        });
+        before - self.faces.len()
+    }
+}
+
+/// Edges that keep a mesh from being watertight. Every edge of a watertight mesh is shared by
+/// exactly two faces.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Watertightness {
+    /// Edges of only one face, around holes and the rims of open surfaces.
+    pub boundary_edges: usize,
+    /// Edges shared by more than two faces.
+    pub non_manifold_edges: usize,
+}
+
+impl Watertightness {
+    pub fn is_watertight(&self) -> bool {
+        self.boundary_edges == 0 && self.non_manifold_edges == 0
+    }
+}
+
+/// Share vertices between triangles that are exactly the same, bit for bit, so
+/// [`IndexedMesh::triangles`] gives back exactly `triangles`. Unlike [`weld`] with an `epsilon` of
+/// 0, -0.0 is kept apart from 0.0.
+pub fn index(triangles: &[Triangle]) -> IndexedMesh {
+    let mut ret = IndexedMesh::default();
+    let mut seen = HashMap::<[u32; 3], usize>::new();
+    for triangle in triangles {
+        let face = triangle.map(|v| {
+            *seen.entry(v.map(f32::to_bits).into()).or_insert_with(|| {
+                ret.vertices.push(v);
+                ret.vertices.len() - 1
+            })
+        });
+        ret.faces.push(face);
+    }
+    ret
+}
+
+/// Merge vertices that are within `epsilon` of each other. An `epsilon` of 0 only merges exactly
+/// equal vertices.
+///
+/// Each merged vertex takes the position of the first of its vertices in `triangles`. Triangles
+/// whose vertices get merged together are kept, so the face count always matches the input.
+pub fn weld(triangles: &[Triangle], epsilon: f32) -> IndexedMesh {
+    let mut ret = IndexedMesh::default();
+    // Vertices are bucketed into cubes of side epsilon, so any vertex within epsilon of another is
+    // in the same or a neighboring cube.
+    let mut grid = HashMap::<[i64; 3], Vec<usize>>::new();
+    let cell = |v: &Vector3<f32>| -> [i64; 3] {
+        if epsilon > 0.0 {
+            v.map(|c| (c / epsilon).floor() as i64).into()
+        } else {
+            // adding 0.0 turns -0.0 into 0.0 so they weld together
+            v.map(|c| (c + 0.0).to_bits() as i64).into()
+        }
+    };
+
+    let offsets: &[i64] = if epsilon > 0.0 { &[-1, 0, 1] } else { &[0] };
+
+    for triangle in triangles {
+        let face = triangle.map(|v| {
+            let [x, y, z] = cell(&v);
+            let near = offsets
+                .iter()
+                .flat_map(|dx| offsets.iter().map(move |dy| (dx, dy)))
+                .flat_map(|(dx, dy)| offsets.iter().map(move |dz| [x + dx, y + dy, z + dz]))
+                .filter_map(|neighbor| grid.get(&neighbor))
+                .flatten()
+                .copied()
+                .find(|&i| (ret.vertices[i] - v).norm() <= epsilon);
+            near.unwrap_or_else(|| {
+                ret.vertices.push(v);
+                let i = ret.vertices.len() - 1;
+                grid.entry([x, y, z]).or_default().push(i);
+                i
+            })
+        });
+        ret.faces.push(face);
+    }
+
+    ret
+}
+
+/// Give a vertex a copy of its own on each side of a crease, where faces using it turn by more
+/// than `angle` radians, so shading keeps the edge sharp. The faces using each vertex are grouped
+/// with the first group whose first face is within `angle` of them, and each group gets its
+/// own copy. Degenerate faces have no direction, so join the first group.
+///
+/// Vertices keep their order, with copies together, and ones no face uses are dropped. An `angle`
+/// of pi or more splits nothing.
+pub fn split_creases(mesh: &IndexedMesh, angle: f32) -> IndexedMesh {
+    let min_cos = match angle < std::f32::consts::PI {
+        true => angle.cos(),
+        // rounding could still split faces facing exactly apart
+        false => f32::NEG_INFINITY,
+    };
+    let normals: Vec<Option<Vector3<f32>>> = mesh
+        .faces
+        .iter()
+        .map(|face| {
+            let [a, b, c] = face.map(|i| mesh.vertices[i]);
+            (b - a).cross(&(c - a)).try_normalize(f32::MIN_POSITIVE)
+        })
+        .collect();
+    // each vertex's uses, as the face and which of its corners
+    let mut uses = vec![Vec::new(); mesh.vertices.len()];
+    for (face, corners) in mesh.faces.iter().enumerate() {
+        for (corner, &v) in corners.iter().enumerate() {
+            uses[v].push((face, corner));
+        }
+    }
+
+    let mut ret = IndexedMesh {
+        vertices: Vec::with_capacity(mesh.vertices.len()),
+        faces: mesh.faces.clone(),
+    };
+    for (v, uses) in uses.iter().enumerate() {
+        // the direction of each group's first face that has one, and the group's copy
+        let mut groups: Vec<(Option<Vector3<f32>>, usize)> = Vec::new();
+        for &(face, corner) in uses {
+            let normal = normals[face];
+            let group = groups.iter_mut().find(|(first, _)| match (first, normal) {
+                (Some(first), Some(normal)) => first.dot(&normal) >= min_cos,
+                _ => true,
+            });
+            ret.faces[face][corner] = match group {
+                Some((first, copy)) => {
+                    *first = first.or(normal);
+                    *copy
+                }
+                None => {
+                    ret.vertices.push(mesh.vertices[v]);
+                    groups.push((normal, ret.vertices.len() - 1));
+                    ret.vertices.len() - 1
+                }
+            };
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::cube;
+
+    #[test]
+    fn weld_cube() {
+        let cube = cube();
+        let welded = weld(&cube, 0.0);
+        assert_eq!(welded.vertices.len(), 8);
+        assert_eq!(welded.faces.len(), 12);
+        assert_eq!(welded.triangles().collect::<Vec<_>>(), cube);
+        assert!(welded.watertightness().is_watertight());
+    }
+
+    #[test]
+    fn dedup_cube() {
+        let mut cube = cube();
+        let [a, b, c] = cube[3];
+        cube.extend([cube[0], [c, b, a], [b, c, a]]);
+        let mut welded = weld(&cube, 0.0);
+        assert_eq!(welded.dedup_faces(), 3);
+        assert_eq!(welded.triangles().collect::<Vec<_>>(), &cube[..12]);
+    }
+
+    #[test]
+    fn index_cube() {
+        let mut cube = cube();
+        cube[0][0].x = -0.0;
+        let indexed = index(&cube);
+        assert_eq!(indexed.vertices.len(), 9);
+        assert_eq!(indexed.triangles().collect::<Vec<_>>(), cube);
+    }
+
+    #[test]
+    fn weld_within_epsilon() {
+        let mut cube = cube();
+        // nudge every vertex reference by a different tiny amount
+        for (i, v) in cube.iter_mut().flatten().enumerate() {
+            v.x += i as f32 * 1e-5;
+        }
+        assert_eq!(weld(&cube, 1e-3).vertices.len(), 8);
+        assert!(weld(&cube, 1e-7).vertices.len() > 8);
+        assert!(weld(&cube, 0.0).vertices.len() > 8);
+        assert!(!weld(&cube, 0.0).watertightness().is_watertight());
+    }
+
+    #[test]
+    fn split_cube_creases() {
+        let cube = cube();
+        let welded = weld(&cube, 0.0);
+        // every corner is on three sides, each of which gets its own copy
+        let split = split_creases(&welded, 0.1);
+        assert_eq!(split.vertices.len(), 24);
+        assert_eq!(split.triangles().collect::<Vec<_>>(), cube);
+        assert_eq!(split_creases(&welded, std::f32::consts::PI), welded);
+    }
+}
@@ -0,0 +1,72 @@
+use nalgebra::Vector3;
+use std::collections::HashSet;
+
+use crate::{perpendicular, IndexedMesh, Mesh, Triangle};
+
+/// A strut along every edge of `mesh` in place of its faces, each a closed triangular prism whose
+/// corners are `radius` from the edge, for seeing the edges or printing a lattice. Struts meet at
+/// the vertices by overlapping rather than being joined, so the result has pieces inside each
+/// other. Each face gets its own three struts, doubling up on shared edges unless `shared_once`,
+/// when an edge gets one strut however many faces share it. Edges of no length are skipped.
+pub fn wireframe(mesh: &IndexedMesh, radius: f32, shared_once: bool) -> Mesh {
+    let mut seen = HashSet::new();
+    let mut ret = Vec::new();
+    for &[a, b, c] in &mesh.faces {
+        for (from, to) in [(a, b), (b, c), (c, a)] {
+            if shared_once && !seen.insert((from.min(to), from.max(to))) {
+                continue;
+            }
+            ret.extend(strut(mesh.vertices[from], mesh.vertices[to], radius));
+        }
+    }
+    ret.into()
+}
+
+/// The eight triangles of a prism from `a` to `b`, facing outward.
+fn strut(a: Vector3<f32>, b: Vector3<f32>, radius: f32) -> Vec<Triangle> {
+    let Some(along) = (b - a).try_normalize(f32::MIN_POSITIVE) else {
+        return Vec::new();
+    };
+    let u = perpendicular(&along);
+    let w = along.cross(&u);
+    // anticlockwise around the edge looking back from b
+    let ring = [0.0, 1.0, 2.0].map(|k: f32| {
+        let angle = k * std::f32::consts::TAU / 3.0;
+        (u * angle.cos() + w * angle.sin()) * radius
+    });
+    let [a0, a1, a2] = ring.map(|offset| a + offset);
+    let [b0, b1, b2] = ring.map(|offset| b + offset);
+    vec![
+        [a0, a1, b1],
+        [a0, b1, b0],
+        [a1, a2, b2],
+        [a1, b2, b1],
+        [a2, a0, b0],
+        [a2, b0, b2],
+        [a0, a2, a1],
+        [b0, b1, b2],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{orient, weld};
+
+    #[test]
+    fn struts_are_closed_and_face_out() {
+        let v = Vector3::new;
+        let tetrahedron = weld(&crate::tetrahedron(), 0.0);
+        assert_eq!(wireframe(&tetrahedron, 0.05, false).len(), 4 * 3 * 8);
+        let mut wires = wireframe(&tetrahedron, 0.05, true);
+        assert_eq!(wires.len(), 6 * 8);
+        assert_eq!(weld(&wires, 0.0).watertightness(), Default::default());
+        // already wound outward, so there's nothing to flip
+        assert_eq!(orient(&mut wires), 0);
+        // a collapsed face has one edge there and back again, and one of no length
+        let [o, x] = [v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0)];
+        let collapsed = weld(&[[o, o, x]], 0.0);
+        assert_eq!(wireframe(&collapsed, 0.05, false).len(), 2 * 8);
+        assert_eq!(wireframe(&collapsed, 0.05, true).len(), 8);
+    }
+}
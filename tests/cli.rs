@@ -0,0 +1,118 @@
+//! Runs the command line tool on options it should turn down, and on `convert`, to catch checks
+//! that quietly stop happening.
+
+use fractulate::format::{obj, stl};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+/// A fresh directory for `test` to write into.
+fn scratch(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("fractulate-{}-{test}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_fractulate"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Panics unless the run failed saying `expected`.
+fn assert_refused(output: &Output, expected: &str) {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "should have failed");
+    assert!(stderr.contains(expected), "{stderr}");
+}
+
+fn assert_succeeded(output: &Output) {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn convert_writes_the_same_triangles() {
+    let dir = scratch("convert");
+    let input = fixture("tetrahedron.stl");
+    let converted = dir.join("tetrahedron.obj");
+    assert_succeeded(&run(&[
+        "convert",
+        input.to_str().unwrap(),
+        converted.to_str().unwrap(),
+    ]));
+    let original = stl::read(&std::fs::read(&input).unwrap()).unwrap();
+    let converted = obj::read(&std::fs::read(&converted).unwrap()).unwrap();
+    assert_eq!(converted.len(), original.len());
+}
+
+#[test]
+fn convert_refuses_growth_options() {
+    let dir = scratch("convert-growth");
+    let input = fixture("tetrahedron.stl");
+    let output = dir.join("out.stl");
+    for option in [
+        &["--depth", "3"][..],
+        &["--children", "2"],
+        &["--rotate-children"],
+    ] {
+        let args = [
+            option,
+            &["convert", input.to_str().unwrap(), output.to_str().unwrap()],
+        ];
+        let name = option[0].trim_start_matches("--");
+        assert_refused(&run(&args.concat()), &format!("doesn't take --{name}"));
+    }
+    assert!(!output.exists());
+}
+
+#[test]
+fn convert_takes_header_text() {
+    let dir = scratch("convert-header");
+    let output = dir.join("out.stl");
+    assert_succeeded(&run(&[
+        "convert",
+        "--header-text",
+        "hi",
+        fixture("tetrahedron.stl").to_str().unwrap(),
+        output.to_str().unwrap(),
+    ]));
+    let written = std::fs::read(&output).unwrap();
+    assert_eq!(stl::header(&written).as_deref(), Some("hi"));
+    assert_eq!(stl::read(&written).unwrap().len(), 4);
+}
+
+#[test]
+fn params_files_are_checked() {
+    let dir = scratch("params");
+    let input = fixture("tetrahedron.stl");
+    for (params, expected) in [
+        (
+            r#"{"jitter": -1.0}"#,
+            "jitter must be a non-negative number",
+        ),
+        (
+            r#"{"min_triangle_area": -1.0}"#,
+            "min_triangle_area must be a non-negative number",
+        ),
+        (
+            r#"{"normal_filter": {"direction": [0.0, 0.0, 1.0], "min_cos": 2.0}}"#,
+            "normal filter min_cos must be between -1 and 1",
+        ),
+    ] {
+        let path = dir.join("params.json");
+        std::fs::write(&path, params).unwrap();
+        let output = run(&["--params", path.to_str().unwrap(), input.to_str().unwrap()]);
+        assert_refused(&output, expected);
+    }
+}
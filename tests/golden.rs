@@ -0,0 +1,57 @@
+//! Runs the command line tool end to end and compares its output against fractals grown before, to
+//! catch changes to what a given seed grows.
+//!
+//! After changing the output on purpose, regenerate the fixture by running the tool with [`ARGS`],
+//! and check the difference looks right.
+
+use fractulate::format::stl;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Grows `tetrahedron-grown.stl` from `tetrahedron.stl`.
+const ARGS: &[&str] = &[
+    "--seed",
+    "1",
+    "--depth",
+    "2",
+    "--children",
+    "3",
+    "--rotate-children",
+    "--placement",
+    "random",
+    "--stl-ascii",
+];
+
+/// Output is compared to within this, to allow for changes in floating point rounding.
+const EPSILON: f32 = 1e-5;
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+#[test]
+fn grows_the_same_tetrahedron() {
+    let output = Command::new(env!("CARGO_BIN_EXE_fractulate"))
+        .args(ARGS)
+        .arg(fixture("tetrahedron.stl"))
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let grown = stl::read(&output.stdout).unwrap();
+    let golden = stl::read(&std::fs::read(fixture("tetrahedron-grown.stl")).unwrap()).unwrap();
+    assert_eq!(grown.len(), golden.len());
+    for (i, (grown, golden)) in grown.iter().zip(golden.iter()).enumerate() {
+        for (a, b) in grown.iter().zip(golden) {
+            assert!(
+                (a - b).amax() <= EPSILON,
+                "triangle {i} moved from {golden:?} to {grown:?}"
+            );
+        }
+    }
+}
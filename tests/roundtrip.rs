@@ -0,0 +1,64 @@
+//! Reads grown fractals back in after writing them as STL, to catch output other readers would
+//! choke on.
+
+use fractulate::format::stl;
+use fractulate::{grow, GrowthParams, Mesh, Triangle};
+use nalgebra::Vector3;
+use rand::SeedableRng;
+use rand_xoshiro::Xoshiro256StarStar;
+
+/// How far a vertex may move through ascii STL, which prints the shortest text that parses back
+/// to the same float, so this is only slack for rounding.
+const EPSILON: f32 = 1e-6;
+
+/// Unit tetrahedron with outward facing triangles.
+fn tetrahedron() -> Mesh {
+    let o = Vector3::zeros();
+    let [x, y, z] = [Vector3::x(), Vector3::y(), Vector3::z()];
+    Mesh::new(vec![[o, y, x], [o, x, z], [o, z, y], [x, y, z]])
+}
+
+fn grown() -> Mesh {
+    let tetrahedron = tetrahedron();
+    let params = GrowthParams {
+        depth: 3,
+        num_children: 3,
+        rotate_children: true,
+        ..Default::default()
+    };
+    let mut rng = Xoshiro256StarStar::seed_from_u64(1);
+    grow(&tetrahedron, &tetrahedron, &params, &mut rng).unwrap()
+}
+
+fn assert_same(read: &[Triangle], written: &[Triangle]) {
+    assert_eq!(read.len(), written.len());
+    for (i, (read, written)) in read.iter().zip(written).enumerate() {
+        for (a, b) in read.iter().zip(written) {
+            assert!(
+                (a - b).amax() <= EPSILON,
+                "triangle {i} was written as {written:?} but read as {read:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn binary_stl_round_trips() {
+    let mesh = grown();
+    let mut out = Vec::new();
+    stl::write(&mut out, Some("solid looking header"), &mesh).unwrap();
+    assert_eq!(out.len(), stl::HEADER_LEN + 4 + 50 * mesh.len());
+    assert_same(&stl::read(&out).unwrap(), &mesh);
+    let (read, header) = stl::read_binary(&out[..]).unwrap();
+    assert_same(&read, &mesh);
+    assert_eq!(header.as_deref(), Some("looking header"));
+}
+
+#[test]
+fn ascii_stl_round_trips() {
+    let mesh = grown();
+    let mut out = Vec::new();
+    stl::write_ascii(&mut out, Some("grown"), mesh.iter().copied()).unwrap();
+    assert_same(&stl::read(&out).unwrap(), &mesh);
+    assert_eq!(stl::header(&out).as_deref(), Some("grown"));
+}